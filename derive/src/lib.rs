@@ -0,0 +1,549 @@
+//! The attribute macro behind the `proc-macro` feature of
+//! [diesel-autoincrement-new-struct](https://docs.rs/diesel_autoincrement_new_struct)
+//!
+//! `#[new_insertable]` checks the struct and its `#[new(...)]` attributes,
+//! pointing at the tokens that are wrong when they are, and then hands the
+//! struct to `NewInsertable!`, which generates everything as usual. Use it
+//! through the re-export in `diesel_autoincrement_new_struct`, as the
+//! expansion refers to that crate by name.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DeriveInput, Fields, GenericParam, Ident, Lit, LitStr, Token, Type,
+    WherePredicate,
+};
+
+#[derive(Clone, Copy)]
+enum Kind {
+    /// `#[new(builder)]`
+    Flag,
+    /// `#[new(allow(...))]`
+    List,
+    /// `#[new(group = "...")]`
+    Str,
+    /// `#[new(example = 42)]`
+    Lit,
+    /// `#[new(doc = "..." "{Entity}")]`
+    Lits,
+    /// `#[new(name = CreateUser)]`
+    Ident,
+    /// `#[new(type = Vec<u8>)]`
+    Type,
+    /// `#[new(pg_ty(feature = "postgres") = uuid::Uuid)]`
+    CfgType,
+    /// `#[new(default)]` or `#[new(default = String::from("open"))]`
+    OptionalExpr,
+}
+
+const STRUCT_OPTIONS: &[(&str, Kind)] = &[
+    ("allow", Kind::List),
+    ("append_doc", Kind::Lits),
+    ("attr", Kind::List),
+    ("builder", Kind::Flag),
+    ("cfg", Kind::List),
+    ("check_for_backend", Kind::List),
+    ("check_id", Kind::Flag),
+    ("check_nullable", Kind::Flag),
+    ("conversions", Kind::Flag),
+    ("cqrs", Kind::Flag),
+    ("create", Kind::Flag),
+    ("derive", Kind::List),
+    ("diff", Kind::Flag),
+    ("display", Kind::Flag),
+    ("doc", Kind::Lits),
+    ("dynamic", Kind::Flag),
+    ("event", Kind::Flag),
+    ("example_attr", Kind::Ident),
+    ("from_form", Kind::Flag),
+    ("from_json", Kind::Flag),
+    ("graphql", Kind::List),
+    ("group", Kind::Str),
+    ("manual_insertable", Kind::Flag),
+    ("name", Kind::Ident),
+    ("no_id", Kind::Flag),
+    ("proptest", Kind::Flag),
+    ("raw_insert", Kind::Flag),
+    ("rename", Kind::List),
+    ("retry", Kind::Flag),
+    ("treat_none_as_null", Kind::Flag),
+    ("update_from_new", Kind::Flag),
+    ("update_struct", Kind::Flag),
+    ("verify_schema", Kind::Flag),
+    ("wasm", Kind::Flag),
+    ("with_id", Kind::Flag),
+];
+
+const FIELD_OPTIONS: &[(&str, Kind)] = &[
+    ("default", Kind::OptionalExpr),
+    ("diesel", Kind::List),
+    ("drop_deprecated", Kind::Flag),
+    ("example", Kind::Lit),
+    ("group", Kind::Str),
+    ("immutable", Kind::Flag),
+    ("natural_key", Kind::Flag),
+    ("pg_ty", Kind::CfgType),
+    ("primary_key", Kind::Flag),
+    ("redact", Kind::Flag),
+    ("skip", Kind::Flag),
+    ("sqlite_ty", Kind::CfgType),
+    ("treat_none_as_null", Kind::Flag),
+    ("type", Kind::Type),
+];
+
+// The same options as in `__diesel_new_generic_opt!`, which the tests of
+// diesel-autoincrement-new-struct check along with the two lists above
+const UNSUPPORTED_ON_GENERICS: &[&str] = &[
+    "builder",
+    "check_for_backend",
+    "conversions",
+    "cqrs",
+    "create",
+    "default",
+    "diff",
+    "display",
+    "dynamic",
+    "event",
+    "from_form",
+    "from_json",
+    "graphql",
+    "proptest",
+    "raw_insert",
+    "retry",
+    "update_from_new",
+    "update_struct",
+    "verify_schema",
+    "with_id",
+];
+
+/// Generate a New struct for Diesel insertions without an 'id' field
+///
+/// The same as `#[apply(NewInsertable!)]`, with `#[new(...)]` options on the
+/// struct either given as they are or as the arguments of the attribute, e.g.
+/// `#[new_insertable(builder)]`. A `where` clause on the struct is moved onto
+/// its generic parameters.
+#[proc_macro_attribute]
+pub fn new_insertable(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The names of the options in the lists above, as
+/// `(struct options, field options, options unsupported on generics)`, so
+/// that diesel-autoincrement-new-struct can check them against its macros
+#[doc(hidden)]
+#[proc_macro]
+pub fn __known_options(_: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let struct_options = STRUCT_OPTIONS.iter().map(|(name, _)| name);
+    let field_options = FIELD_OPTIONS.iter().map(|(name, _)| name);
+    let unsupported_on_generics = UNSUPPORTED_ON_GENERICS.iter();
+    quote! {
+        (
+            &[#(#struct_options),*] as &[&str],
+            &[#(#field_options),*] as &[&str],
+            &[#(#unsupported_on_generics),*] as &[&str],
+        )
+    }
+    .into()
+}
+
+fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let mut input: DeriveInput = syn::parse2(item)?;
+    if !args.is_empty() {
+        input.attrs.insert(0, syn::parse_quote!(#[new(#args)]));
+    }
+
+    let mut errors = Vec::new();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields,
+            fields => {
+                return Err(syn::Error::new(
+                    fields.span(),
+                    "expected a struct with named fields",
+                ))
+            }
+        },
+        Data::Enum(data) => {
+            return Err(syn::Error::new(
+                data.enum_token.span,
+                "expected a struct, found an enum",
+            ))
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "expected a struct, found a union",
+            ))
+        }
+    };
+
+    let mut options = Vec::new();
+    for attr in new_attrs(&input.attrs) {
+        match parse_options(attr, STRUCT_OPTIONS) {
+            Ok(names) => options.extend(names),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if !input.attrs.iter().any(has_table_name) {
+        errors.push(syn::Error::new(
+            input.ident.span(),
+            "expected `#[diesel(table_name = ...)]` on the struct, for the table of the New struct",
+        ));
+    }
+
+    let mut field_options = Vec::new();
+    for field in &fields.named {
+        for attr in new_attrs(&field.attrs) {
+            match parse_options(attr, FIELD_OPTIONS) {
+                Ok(names) => field_options.extend(names),
+                Err(error) => errors.push(error),
+            }
+        }
+    }
+    let has_primary_key = field_options.iter().any(|name| name == "primary_key");
+
+    let starts_with_id = fields
+        .named
+        .first()
+        .is_some_and(|field| field.ident.as_ref().is_some_and(|ident| ident == "id"));
+    if !starts_with_id && !has_primary_key && !options.iter().any(|name| name == "no_id") {
+        let span = fields
+            .named
+            .first()
+            .map_or(fields.brace_token.span.join(), Spanned::span);
+        errors.push(syn::Error::new(
+            span,
+            "expected the first field of the struct to be `id`, a field with `#[new(primary_key)]`, or `#[new(no_id)]` on a struct without one",
+        ));
+    }
+
+    if !input.generics.params.is_empty() {
+        for name in options.iter().chain(&field_options) {
+            if UNSUPPORTED_ON_GENERICS.iter().any(|option| name == option) {
+                errors.push(syn::Error::new(
+                    name.span(),
+                    format!("`#[new({name})]` can't be used on a struct with generic parameters"),
+                ));
+            }
+        }
+    }
+
+    for param in &input.generics.params {
+        let default = match param {
+            GenericParam::Type(param) => param.eq_token,
+            GenericParam::Const(param) => param.eq_token,
+            GenericParam::Lifetime(_) => None,
+        };
+        if let Some(eq_token) = default {
+            errors.push(syn::Error::new(
+                eq_token.span,
+                "defaults of generic parameters can't be carried onto the impls of the New struct",
+            ));
+        }
+    }
+
+    // `NewInsertable!` takes the bounds on the parameters only, as Diesel's
+    // derives can't take a `where` clause
+    if let Some(where_clause) = input.generics.where_clause.take() {
+        for predicate in where_clause.predicates {
+            if let Err(error) = move_onto_param(&mut input.generics.params, predicate) {
+                errors.push(error);
+            }
+        }
+    }
+
+    if let Some(error) = errors.into_iter().reduce(|mut errors, error| {
+        errors.combine(error);
+        errors
+    }) {
+        return Err(error);
+    }
+
+    Ok(quote! {
+        ::diesel_autoincrement_new_struct::NewInsertable! { #input }
+    })
+}
+
+fn new_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("new"))
+}
+
+fn has_table_name(attr: &Attribute) -> bool {
+    match &attr.meta {
+        syn::Meta::List(list) if list.path.is_ident("diesel") => list
+            .tokens
+            .clone()
+            .into_iter()
+            .any(|token| matches!(token, TokenTree::Ident(ident) if ident == "table_name")),
+        _ => false,
+    }
+}
+
+/// Checks each option of a `#[new(...)]` against the options that are known,
+/// returning their names
+fn parse_options(attr: &Attribute, known: &[(&str, Kind)]) -> syn::Result<Vec<Ident>> {
+    attr.parse_args_with(|input: ParseStream| {
+        let mut names = Vec::new();
+        while !input.is_empty() {
+            let name = Ident::parse_any(input)?;
+            let Some((_, kind)) = known.iter().find(|(option, _)| name == option) else {
+                let expected = known
+                    .iter()
+                    .map(|(option, _)| format!("`{option}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("unknown `#[new]` option `{name}`, expected one of {expected}"),
+                ));
+            };
+
+            match kind {
+                Kind::Flag => {}
+                // Without syn's `full` feature expressions can't be parsed, so
+                // the tokens up to the next option are left to the compiler
+                Kind::OptionalExpr => {
+                    if input.parse::<Option<Token![=]>>()?.is_some() {
+                        if input.is_empty() || input.peek(Token![,]) {
+                            return Err(input.error("expected an expression"));
+                        }
+                        while !input.is_empty() && !input.peek(Token![,]) {
+                            input.parse::<TokenTree>()?;
+                        }
+                    }
+                }
+                Kind::List => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    content.parse::<TokenStream>()?;
+                }
+                Kind::CfgType => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    content.parse::<syn::Meta>()?;
+                    input.parse::<Token![=]>()?;
+                    input.parse::<Type>()?;
+                }
+                Kind::Str | Kind::Lit | Kind::Lits | Kind::Ident | Kind::Type => {
+                    input.parse::<Token![=]>()?;
+                    match kind {
+                        Kind::Str => {
+                            input.parse::<LitStr>()?;
+                        }
+                        Kind::Lit => {
+                            input.parse::<Lit>()?;
+                        }
+                        Kind::Lits => {
+                            input.parse::<Lit>()?;
+                            while !input.is_empty() && !input.peek(Token![,]) {
+                                input.parse::<TokenTree>()?;
+                            }
+                        }
+                        Kind::Ident => {
+                            input.parse::<Ident>()?;
+                        }
+                        _ => {
+                            input.parse::<Type>()?;
+                        }
+                    }
+                }
+            }
+
+            names.push(name);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(names)
+    })
+}
+
+fn move_onto_param(
+    params: &mut Punctuated<GenericParam, Token![,]>,
+    predicate: WherePredicate,
+) -> syn::Result<()> {
+    let span = predicate.span();
+    let moved = match predicate {
+        WherePredicate::Type(predicate) if predicate.lifetimes.is_none() => {
+            let ident = match &predicate.bounded_ty {
+                Type::Path(ty) if ty.qself.is_none() => ty.path.get_ident(),
+                _ => None,
+            };
+            params.iter_mut().any(|param| match param {
+                GenericParam::Type(param) if Some(&param.ident) == ident => {
+                    param.colon_token.get_or_insert_with(Default::default);
+                    param.bounds.extend(predicate.bounds.clone());
+                    true
+                }
+                _ => false,
+            })
+        }
+        WherePredicate::Lifetime(predicate) => params.iter_mut().any(|param| match param {
+            GenericParam::Lifetime(param) if param.lifetime == predicate.lifetime => {
+                param.colon_token.get_or_insert_with(Default::default);
+                param.bounds.extend(predicate.bounds.clone());
+                true
+            }
+            _ => false,
+        }),
+        _ => false,
+    };
+
+    if moved {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            "expected a bound on a generic parameter of the struct, as the bounds of a `where` clause are moved onto the parameters",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(item: TokenStream) -> String {
+        let errors = expand(TokenStream::new(), item).unwrap_err();
+        errors
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn it_moves_the_where_clause_onto_the_parameters() {
+        let expanded = expand(
+            TokenStream::new(),
+            quote! {
+                #[diesel(table_name = users)]
+                pub struct User<'a, N> where N: AsRef<str> + Clone, 'a: 'static {
+                    id: i32,
+                    name: &'a N,
+                }
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            expanded.to_string(),
+            quote! {
+                ::diesel_autoincrement_new_struct::NewInsertable! {
+                    #[diesel(table_name = users)]
+                    pub struct User<'a: 'static, N: AsRef<str> + Clone> {
+                        id: i32,
+                        name: &'a N,
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn it_passes_its_arguments_on_as_options() {
+        let expanded = expand(
+            quote!(builder),
+            quote! {
+                #[diesel(table_name = users)]
+                pub struct User { id: i32 }
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            expanded.to_string(),
+            quote! {
+                ::diesel_autoincrement_new_struct::NewInsertable! {
+                    #[new(builder)]
+                    #[diesel(table_name = users)]
+                    pub struct User { id: i32 }
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn it_rejects_unknown_options() {
+        let message = error(quote! {
+            #[new(group = "signup")]
+            #[diesel(table_name = users)]
+            pub struct User {
+                id: i32,
+                #[new(gruop = "signup")]
+                name: String,
+            }
+        });
+
+        assert!(
+            message.starts_with("unknown `#[new]` option `gruop`, expected one of `default`"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn it_rejects_malformed_options() {
+        assert_eq!(
+            error(quote! {
+                #[new(group = signup)]
+                #[diesel(table_name = users)]
+                pub struct User { id: i32 }
+            }),
+            "expected string literal"
+        );
+        assert_eq!(
+            error(quote! {
+                #[diesel(table_name = users)]
+                pub struct User {
+                    id: i32,
+                    #[new(default =, skip)]
+                    name: String,
+                }
+            }),
+            "expected an expression"
+        );
+    }
+
+    #[test]
+    fn it_requires_a_table_name_and_an_id() {
+        let message = error(quote! {
+            #[derive(Queryable)]
+            pub struct User { name: String }
+        });
+
+        assert!(
+            message.contains("`#[diesel(table_name = ...)]`"),
+            "{message}"
+        );
+        assert!(
+            message.contains("the first field of the struct to be `id`"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn it_rejects_options_that_dont_support_generics() {
+        assert_eq!(
+            error(quote! {
+                #[new(builder)]
+                #[diesel(table_name = users)]
+                pub struct User<'a> { id: i32, name: &'a str }
+            }),
+            "`#[new(builder)]` can't be used on a struct with generic parameters"
+        );
+    }
+}
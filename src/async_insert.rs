@@ -0,0 +1,140 @@
+//! Inserting New structs with [diesel-async](https://docs.rs/diesel-async)
+
+use std::future::Future;
+
+use diesel::query_builder::InsertStatement;
+use diesel::{Insertable, QueryResult};
+use diesel_async::methods::{ExecuteDsl, LoadQuery};
+use diesel_async::{AsyncConnectionCore, RunQueryDsl};
+use futures_util::{Stream, StreamExt};
+
+use crate::{InsertNew, InstrumentedTable};
+
+/// Extension methods for inserting New structs with an async connection
+///
+/// Like [InsertNew], this is implemented for every struct generated by this
+/// crate, and has the same methods for use on async stacks without wrapping
+/// each insert in `spawn_blocking`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use diesel_autoincrement_new_struct::prelude::*;
+/// use diesel_autoincrement_new_struct::InsertNewAsync;
+/// use diesel::prelude::*;
+/// use diesel_async::AsyncPgConnection;
+/// use futures_util::StreamExt;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: i32,
+///     name: String
+/// }
+///
+/// async fn create(conn: &mut AsyncPgConnection) -> QueryResult<User> {
+///     NewUser { name: String::from("Ferris") }.insert_returning_async(conn).await
+/// }
+///
+/// async fn import(conn: &mut AsyncPgConnection) -> QueryResult<()> {
+///     let rows = futures_util::stream::iter(0..250).map(|i| NewUser { name: format!("Ferris {}", i) });
+///     let inserted = NewUser::ingest(rows, 100, conn).await?;
+///     assert_eq!(inserted, 250);
+///
+///     Ok(())
+/// }
+/// ```
+pub trait InsertNewAsync: InsertNew + Send {
+    /// Inserts into the table and returns the number of rows inserted
+    fn insert_async<Conn>(self, conn: &mut Conn) -> impl Future<Output = QueryResult<usize>> + Send
+    where
+        Conn: AsyncConnectionCore,
+        Self::Table: InstrumentedTable<Conn::Backend>,
+        InsertStatement<Self::Table, Self::Values>: ExecuteDsl<Conn>,
+    {
+        async move {
+            crate::instrument::insert_async::<Self::Table, Conn::Backend, _>(
+                diesel::insert_into(Self::table())
+                    .values(self)
+                    .execute(conn),
+            )
+            .await
+        }
+    }
+
+    /// Inserts into the table and returns the inserted row, e.g. as the
+    /// struct that the New struct was generated from
+    ///
+    /// This needs a backend with support for `RETURNING` clauses.
+    fn insert_returning_async<'conn, 'query, Conn, U>(
+        self,
+        conn: &'conn mut Conn,
+    ) -> impl Future<Output = QueryResult<U>> + Send
+    where
+        Conn: AsyncConnectionCore,
+        Self::Table: InstrumentedTable<Conn::Backend>,
+        U: Send + 'conn,
+        InsertStatement<Self::Table, Self::Values>: LoadQuery<'query, Conn, U> + 'query,
+    {
+        async move {
+            crate::instrument::insert_async::<Self::Table, Conn::Backend, _>(
+                diesel::insert_into(Self::table())
+                    .values(self)
+                    .get_result(conn),
+            )
+            .await
+        }
+    }
+
+    /// Inserts every row from an async stream in batches of `chunk_size` rows,
+    /// and returns the number of rows inserted
+    ///
+    /// The stream isn't polled while a batch is being inserted, so a producer
+    /// that is faster than the database is held back rather than buffered.
+    /// As with [`InsertNew::insert_all`], `chunk_size` times the number of
+    /// fields has to stay under the backend's limit on binds, and a
+    /// `chunk_size` of 0 is taken as 1, inserting the rows one at a time.
+    ///
+    /// Diesel only splits batch inserts into single rows for SQLite on its own
+    /// `SqliteConnection`, so this can't be used with SQLite through
+    /// `SyncConnectionWrapper`.
+    fn ingest<S, Conn>(
+        rows: S,
+        chunk_size: usize,
+        conn: &mut Conn,
+    ) -> impl Future<Output = QueryResult<usize>> + Send
+    where
+        S: Stream<Item = Self> + Send,
+        Conn: AsyncConnectionCore,
+        Self::Table: InstrumentedTable<Conn::Backend>,
+        Vec<Self>: Insertable<Self::Table>,
+        InsertStatement<Self::Table, <Vec<Self> as Insertable<Self::Table>>::Values>:
+            ExecuteDsl<Conn>,
+    {
+        async move {
+            let mut chunks = std::pin::pin!(rows.chunks(chunk_size.max(1)));
+            let mut inserted = 0;
+
+            while let Some(chunk) = chunks.next().await {
+                inserted += crate::instrument::insert_async::<Self::Table, Conn::Backend, _>(
+                    diesel::insert_into(Self::table())
+                        .values(chunk)
+                        .execute(conn),
+                )
+                .await?;
+            }
+
+            Ok(inserted)
+        }
+    }
+}
+
+impl<T> InsertNewAsync for T where T: InsertNew + Send {}
@@ -0,0 +1,160 @@
+use diesel::connection::TransactionManager;
+use diesel::query_builder::InsertStatement;
+use diesel::query_dsl::methods::ExecuteDsl;
+use diesel::{Connection, Insertable, QueryResult};
+
+use crate::{InsertNew, InstrumentedTable};
+
+/// Buffers New structs and inserts them in batches, for streaming ETL jobs
+/// that would otherwise write the same buffer-and-flush loop for every table
+///
+/// A batch is inserted as a single `INSERT` every time the buffer is full, and
+/// whatever is left is inserted by [`finish`](BatchInserter::finish), or on a
+/// best-effort basis when the inserter is dropped, where errors can't be
+/// reported.
+///
+/// A [`transactional`](BatchInserter::transactional) inserter runs every batch
+/// in one transaction instead, which is only committed by `finish`, so that
+/// either every row is inserted or none are.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::prelude::*;
+/// use diesel_autoincrement_new_struct::BatchInserter;
+/// use diesel::prelude::*;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: i32,
+///     name: String
+/// }
+///
+/// let mut conn = SqliteConnection::establish(":memory:").unwrap();
+/// diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+///     .execute(&mut conn)
+///     .unwrap();
+///
+/// let mut inserter = BatchInserter::new(&mut conn, 100);
+/// for i in 0..250 {
+///     inserter.push(NewUser { name: format!("Ferris {}", i) }).unwrap();
+/// }
+///
+/// assert_eq!(inserter.finish(), Ok(250));
+/// ```
+pub struct BatchInserter<'conn, T, Conn>
+where
+    T: InsertNew,
+    Conn: Connection,
+    T::Table: InstrumentedTable<Conn::Backend>,
+    Vec<T>: Insertable<T::Table>,
+    InsertStatement<T::Table, <Vec<T> as Insertable<T::Table>>::Values>: ExecuteDsl<Conn>,
+{
+    conn: &'conn mut Conn,
+    rows: Vec<T>,
+    batch_size: usize,
+    inserted: usize,
+    transaction: bool,
+}
+
+impl<'conn, T, Conn> BatchInserter<'conn, T, Conn>
+where
+    T: InsertNew,
+    Conn: Connection,
+    T::Table: InstrumentedTable<Conn::Backend>,
+    Vec<T>: Insertable<T::Table>,
+    InsertStatement<T::Table, <Vec<T> as Insertable<T::Table>>::Values>: ExecuteDsl<Conn>,
+{
+    /// Inserts a batch every `batch_size` rows, which times the number of
+    /// fields has to stay under the backend's limit on binds
+    pub fn new(conn: &'conn mut Conn, batch_size: usize) -> Self {
+        let batch_size = batch_size.max(1);
+
+        Self {
+            conn,
+            rows: Vec::with_capacity(batch_size),
+            batch_size,
+            inserted: 0,
+            transaction: false,
+        }
+    }
+
+    /// Like [`new`](BatchInserter::new), but begins a transaction that is
+    /// committed by [`finish`](BatchInserter::finish) and rolled back when the
+    /// inserter is dropped without finishing
+    pub fn transactional(conn: &'conn mut Conn, batch_size: usize) -> QueryResult<Self> {
+        Conn::TransactionManager::begin_transaction(conn)?;
+
+        let mut inserter = Self::new(conn, batch_size);
+        inserter.transaction = true;
+
+        Ok(inserter)
+    }
+
+    /// Adds a row, inserting the batch if it is full
+    pub fn push(&mut self, row: T) -> QueryResult<()> {
+        self.rows.push(row);
+
+        if self.rows.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the rows that are buffered, and returns how many there were
+    ///
+    /// The rows are dropped when they can't be inserted.
+    pub fn flush(&mut self) -> QueryResult<usize> {
+        let rows = std::mem::take(&mut self.rows);
+        let inserted = T::insert_all(rows, self.batch_size, self.conn)?;
+        self.inserted += inserted;
+
+        Ok(inserted)
+    }
+
+    /// The number of rows inserted so far
+    pub fn inserted(&self) -> usize {
+        self.inserted
+    }
+
+    /// Inserts the rows that are left, commits the transaction of a
+    /// transactional inserter, and returns the number of rows inserted
+    pub fn finish(mut self) -> QueryResult<usize> {
+        self.flush()?;
+
+        if self.transaction {
+            // Whatever happens, there is nothing left to roll back on drop
+            self.transaction = false;
+            Conn::TransactionManager::commit_transaction(self.conn)?;
+        }
+
+        Ok(self.inserted)
+    }
+}
+
+impl<T, Conn> Drop for BatchInserter<'_, T, Conn>
+where
+    T: InsertNew,
+    Conn: Connection,
+    T::Table: InstrumentedTable<Conn::Backend>,
+    Vec<T>: Insertable<T::Table>,
+    InsertStatement<T::Table, <Vec<T> as Insertable<T::Table>>::Values>: ExecuteDsl<Conn>,
+{
+    fn drop(&mut self) {
+        if self.transaction {
+            let _ = Conn::TransactionManager::rollback_transaction(self.conn);
+        } else if !self.rows.is_empty() {
+            let _ = self.flush();
+        }
+    }
+}
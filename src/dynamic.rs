@@ -0,0 +1,54 @@
+//! Inserting New structs into tables whose names are only known at runtime
+
+use std::borrow::Borrow;
+
+use diesel::backend::Backend;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::{Insertable, QueryResult, RunQueryDsl};
+use diesel_dynamic_schema::Table;
+
+/// An `INSERT` statement into a table created with [diesel_dynamic_schema]
+///
+/// The column names and values come from the `Insertable` impl for the static
+/// table, so a runtime-named table such as a time-based partition must have
+/// the same columns as the table in the schema.
+#[derive(Debug, Clone)]
+pub struct DynamicInsert<Name, Values> {
+    table: Table<Name>,
+    values: Values,
+}
+
+/// Builds an `INSERT` of `values` into the table called `name`, using the
+/// columns of the static table `T`
+pub fn insert_into<T, Name, V>(name: Name, values: V) -> DynamicInsert<Name, V::Values>
+where
+    V: Insertable<T>,
+{
+    DynamicInsert {
+        table: diesel_dynamic_schema::table(name),
+        values: values.values(),
+    }
+}
+
+impl<Name, Values, DB> QueryFragment<DB> for DynamicInsert<Name, Values>
+where
+    DB: Backend,
+    Name: Borrow<str>,
+    Table<Name>: QueryFragment<DB>,
+    Values: QueryFragment<DB>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, DB>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+        out.push_sql("INSERT INTO ");
+        self.table.walk_ast(out.reborrow())?;
+        out.push_sql(" ");
+        self.values.walk_ast(out.reborrow())
+    }
+}
+
+impl<Name, Values> QueryId for DynamicInsert<Name, Values> {
+    type QueryId = ();
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Name, Values, Conn> RunQueryDsl<Conn> for DynamicInsert<Name, Values> {}
@@ -0,0 +1,17 @@
+//! What `#[new(event, create)]` needs from the New struct
+
+/// The New struct is inserted and then moved into the event, so
+/// `create_with_event` needs a copy of it
+#[diagnostic::on_unimplemented(
+    message = "`create_with_event` needs `{Self}` to be `Clone`",
+    note = "derive `Clone` on the struct, or add it with `#[new(derive(...))]`"
+)]
+pub trait CloneForEvent {
+    fn clone_for_event(&self) -> Self;
+}
+
+impl<T: Clone> CloneForEvent for T {
+    fn clone_for_event(&self) -> Self {
+        self.clone()
+    }
+}
@@ -0,0 +1,130 @@
+//! Building New structs from the key/value pairs of submitted forms
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A field of a form that couldn't be turned into the type of the field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormFieldError {
+    /// A field that isn't an `Option` wasn't in the form
+    Missing {
+        /// Name of the field
+        field: &'static str,
+    },
+    /// The value of a field couldn't be parsed with `FromStr`
+    Invalid {
+        /// Name of the field
+        field: &'static str,
+        /// Type of the field, as written in the struct
+        expected: &'static str,
+        /// The error from `FromStr`
+        message: String,
+    },
+}
+
+impl fmt::Display for FormFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormFieldError::Missing { field } => write!(f, "field `{}` is missing", field),
+            FormFieldError::Invalid {
+                field,
+                expected,
+                message,
+            } => write!(
+                f,
+                "field `{}` is not a valid `{}`: {}",
+                field, expected, message
+            ),
+        }
+    }
+}
+
+/// Error from `TryFrom<&HashMap<String, String>>` for a New struct, with every
+/// field that was missing or invalid rather than just the first one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromFormError {
+    /// The fields that were missing or invalid, in the order of the struct
+    pub errors: Vec<FormFieldError>,
+}
+
+impl fmt::Display for FromFormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+
+            write!(f, "{}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for FromFormError {}
+
+/// Parses a field that has to be in the form, recording an error and returning
+/// `None` when it is missing or invalid
+#[doc(hidden)]
+pub fn required<T>(
+    form: &HashMap<String, String>,
+    field: &'static str,
+    expected: &'static str,
+    errors: &mut Vec<FormFieldError>,
+) -> Option<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match form.get(field) {
+        Some(value) => parse(value, field, expected, errors),
+        None => {
+            errors.push(FormFieldError::Missing { field });
+            None
+        }
+    }
+}
+
+/// Parses an `Option` field, which is `None` when it is missing or empty, as
+/// browsers submit empty inputs as empty strings
+#[doc(hidden)]
+pub fn optional<T>(
+    form: &HashMap<String, String>,
+    field: &'static str,
+    expected: &'static str,
+    errors: &mut Vec<FormFieldError>,
+) -> Option<Option<T>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match form.get(field) {
+        Some(value) if !value.is_empty() => parse(value, field, expected, errors).map(Some),
+        _ => Some(None),
+    }
+}
+
+fn parse<T>(
+    value: &str,
+    field: &'static str,
+    expected: &'static str,
+    errors: &mut Vec<FormFieldError>,
+) -> Option<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(error) => {
+            errors.push(FormFieldError::Invalid {
+                field,
+                expected,
+                message: error.to_string(),
+            });
+            None
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use diesel::associations::HasTable;
+
+/// Links a struct given to this crate's macros to the New struct generated
+/// from it
+///
+/// This is implemented for every struct that `NewInsertable!` is applied to,
+/// so that generic code, such as a repository over several tables, can go from
+/// a model to the struct that inserts it without any glue for each table.
+/// Group structs aren't linked, as there can be any number of them.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::prelude::*;
+/// use diesel_autoincrement_new_struct::{HasNewInsertable, InstrumentedTable};
+/// use diesel::associations::HasTable;
+/// use diesel::prelude::*;
+/// use diesel::query_builder::InsertStatement;
+/// use diesel::query_dsl::LoadQuery;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: i32,
+///     name: String
+/// }
+///
+/// fn create<M>(new: M::New, conn: &mut SqliteConnection) -> QueryResult<M>
+/// where
+///     M: HasNewInsertable,
+///     M::New: InsertNew,
+///     <M::New as HasTable>::Table: InstrumentedTable<diesel::sqlite::Sqlite>,
+///     for<'query> InsertStatement<
+///         <M::New as HasTable>::Table,
+///         <M::New as Insertable<<M::New as HasTable>::Table>>::Values,
+///     >: LoadQuery<'query, SqliteConnection, M>,
+/// {
+///     new.insert_returning(conn)
+/// }
+///
+/// let mut conn = SqliteConnection::establish(":memory:").unwrap();
+/// diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+///     .execute(&mut conn)
+///     .unwrap();
+///
+/// let user = create::<User>(NewUser { name: String::from("Ferris") }, &mut conn).unwrap();
+/// assert_eq!(user.id, 1);
+/// ```
+pub trait HasNewInsertable {
+    /// The New struct, e.g. `NewUser` for `User`
+    type New: HasTable;
+}
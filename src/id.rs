@@ -0,0 +1,49 @@
+/// Types that can be the id of a struct given to this crate's macros
+///
+/// The id field is stripped from the New struct on the assumption that the
+/// database generates it, which only holds for autoincrementing integer keys.
+/// `#[new(check_id)]` fails to compile when the id isn't one of these, which
+/// catches UUID-keyed tables being given to the macros by mistake.
+///
+/// Newtypes around integer ids can opt in by implementing this trait.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::prelude::*;
+/// use diesel_autoincrement_new_struct::AutoincrementId;
+/// use diesel::prelude::*;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// #[derive(Debug, Clone, Copy, diesel_derive_newtype::DieselNewType)]
+/// pub struct UserId(i32);
+///
+/// impl AutoincrementId for UserId {}
+///
+/// #[apply(NewInsertable!)]
+/// #[new(check_id)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: UserId,
+///     name: String
+/// }
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not an autoincrementing id",
+    note = "only integer ids are generated by the database; implement `AutoincrementId` for newtypes around them"
+)]
+pub trait AutoincrementId {}
+
+impl AutoincrementId for i16 {}
+impl AutoincrementId for i32 {}
+impl AutoincrementId for i64 {}
+impl AutoincrementId for u16 {}
+impl AutoincrementId for u32 {}
+impl AutoincrementId for u64 {}
@@ -0,0 +1,184 @@
+use diesel::associations::HasTable;
+use diesel::query_builder::InsertStatement;
+use diesel::query_dsl::methods::ExecuteDsl;
+use diesel::query_dsl::LoadQuery;
+use diesel::{Connection, Insertable, QueryResult, RunQueryDsl};
+
+use crate::InstrumentedTable;
+
+/// Extension methods for inserting New structs into their table
+///
+/// This is implemented for every struct generated by this crate (and anything
+/// else that is both `HasTable` and `Insertable` for that table), so there's no
+/// need to pass the table around at each call site.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::prelude::*;
+/// use diesel::prelude::*;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: i32,
+///     name: String
+/// }
+///
+/// let mut conn = SqliteConnection::establish(":memory:").unwrap();
+/// diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+///     .execute(&mut conn)
+///     .unwrap();
+///
+/// let inserted = NewUser { name: String::from("Ferris") }.insert(&mut conn).unwrap();
+/// assert_eq!(inserted, 1);
+/// ```
+pub trait InsertNew: HasTable + Insertable<<Self as HasTable>::Table> + Sized {
+    /// Inserts into the table and returns the number of rows inserted
+    fn insert<Conn>(self, conn: &mut Conn) -> QueryResult<usize>
+    where
+        Conn: Connection,
+        Self::Table: InstrumentedTable<Conn::Backend>,
+        InsertStatement<Self::Table, Self::Values>: ExecuteDsl<Conn>,
+    {
+        crate::instrument::insert::<Self::Table, Conn::Backend, _>(|| {
+            self.insert_into(Self::table()).execute(conn)
+        })
+    }
+
+    /// Inserts into the table and returns the inserted row, e.g. as the
+    /// struct that the New struct was generated from
+    ///
+    /// This needs a backend with support for `RETURNING` clauses.
+    fn insert_returning<'query, Conn, U>(self, conn: &mut Conn) -> QueryResult<U>
+    where
+        Conn: Connection,
+        Self::Table: InstrumentedTable<Conn::Backend>,
+        InsertStatement<Self::Table, Self::Values>: LoadQuery<'query, Conn, U>,
+    {
+        crate::instrument::insert::<Self::Table, Conn::Backend, _>(|| {
+            self.insert_into(Self::table()).get_result(conn)
+        })
+    }
+
+    /// Inserts every row from an iterator in batches of at most `chunk_size`
+    /// rows, and returns the number of rows inserted
+    ///
+    /// Only one batch is held in memory at a time, so the rows can be streamed
+    /// from a file or another database without collecting them first. Each
+    /// batch is a single `INSERT`, so `chunk_size` times the number of fields
+    /// has to stay under the backend's limit on binds (65535 for Postgres and
+    /// 32766 for SQLite).
+    fn insert_all<I, Conn>(rows: I, chunk_size: usize, conn: &mut Conn) -> QueryResult<usize>
+    where
+        I: IntoIterator<Item = Self>,
+        Conn: Connection,
+        Self::Table: InstrumentedTable<Conn::Backend>,
+        Vec<Self>: Insertable<Self::Table>,
+        InsertStatement<Self::Table, <Vec<Self> as Insertable<Self::Table>>::Values>:
+            ExecuteDsl<Conn>,
+    {
+        let chunk_size = chunk_size.max(1);
+        let mut rows = rows.into_iter();
+        let mut inserted = 0;
+
+        loop {
+            let chunk = rows.by_ref().take(chunk_size).collect::<Vec<_>>();
+            if chunk.is_empty() {
+                return Ok(inserted);
+            }
+
+            inserted += crate::instrument::insert::<Self::Table, Conn::Backend, _>(|| {
+                chunk.insert_into(Self::table()).execute(conn)
+            })?;
+        }
+    }
+}
+
+impl<T> InsertNew for T where T: HasTable + Insertable<<T as HasTable>::Table> {}
+
+/// Object-safe counterpart of [InsertNew], for queues of New structs for
+/// different tables that are drained by a single worker
+///
+/// The connection is a parameter of the trait rather than of the method, so
+/// that `Box<dyn AnyNewInsertable<Conn>>` is a type of its own. It is
+/// implemented for every struct that [InsertNew::insert] works with on `Conn`.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::prelude::*;
+/// use diesel_autoincrement_new_struct::AnyNewInsertable;
+/// use diesel::prelude::*;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// table! {
+///     posts(id) {
+///         id -> Integer,
+///         title -> Text,
+///     }
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: i32,
+///     name: String
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = posts)]
+/// pub struct Post {
+///     id: i32,
+///     title: String
+/// }
+///
+/// let mut conn = SqliteConnection::establish(":memory:").unwrap();
+/// diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+///     .execute(&mut conn)
+///     .unwrap();
+/// diesel::sql_query("CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT NOT NULL)")
+///     .execute(&mut conn)
+///     .unwrap();
+///
+/// let queue: Vec<Box<dyn AnyNewInsertable<SqliteConnection> + Send>> = vec![
+///     Box::new(NewUser { name: String::from("Ferris") }),
+///     Box::new(NewPost { title: String::from("Hello") }),
+/// ];
+///
+/// for row in queue {
+///     assert_eq!(row.insert_boxed(&mut conn), Ok(1));
+/// }
+/// ```
+pub trait AnyNewInsertable<Conn> {
+    /// Inserts into the table and returns the number of rows inserted
+    fn insert_boxed(self: Box<Self>, conn: &mut Conn) -> QueryResult<usize>;
+}
+
+impl<T, Conn> AnyNewInsertable<Conn> for T
+where
+    T: InsertNew,
+    Conn: Connection,
+    T::Table: InstrumentedTable<Conn::Backend>,
+    InsertStatement<T::Table, T::Values>: ExecuteDsl<Conn>,
+{
+    fn insert_boxed(self: Box<Self>, conn: &mut Conn) -> QueryResult<usize> {
+        (*self).insert(conn)
+    }
+}
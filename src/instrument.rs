@@ -0,0 +1,69 @@
+use diesel::backend::Backend;
+use diesel::QueryResult;
+
+use crate::InstrumentedTable;
+
+/// Runs an insert into `Table`, and with the `metrics` feature records it with
+/// the [`metrics`](https://docs.rs/metrics) facade, tagged with the name of the
+/// table as Diesel writes it for `DB`:
+///
+/// - `diesel_new_inserts_total`, a counter that is also tagged with whether the
+///   insert succeeded as `result="ok"` or `result="error"`
+/// - `diesel_new_insert_duration_seconds`, a histogram of how long it took
+pub fn insert<Table, DB, T>(f: impl FnOnce() -> QueryResult<T>) -> QueryResult<T>
+where
+    Table: InstrumentedTable<DB>,
+    DB: Backend,
+{
+    #[cfg(feature = "metrics")]
+    {
+        let start = std::time::Instant::now();
+        let result = f();
+        record::<Table, DB, _>(start, &result);
+
+        result
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    f()
+}
+
+/// [insert] for inserts made with diesel-async
+#[cfg(feature = "async")]
+pub async fn insert_async<Table, DB, T>(
+    f: impl std::future::Future<Output = QueryResult<T>>,
+) -> QueryResult<T>
+where
+    Table: InstrumentedTable<DB>,
+    DB: Backend,
+{
+    #[cfg(feature = "metrics")]
+    {
+        let start = std::time::Instant::now();
+        let result = f.await;
+        record::<Table, DB, _>(start, &result);
+
+        result
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    f.await
+}
+
+#[cfg(feature = "metrics")]
+fn record<Table, DB, T>(start: std::time::Instant, result: &QueryResult<T>)
+where
+    Table: InstrumentedTable<DB>,
+    DB: Backend,
+{
+    let table = Table::name();
+
+    ::metrics::histogram!("diesel_new_insert_duration_seconds", "table" => table)
+        .record(start.elapsed().as_secs_f64());
+    ::metrics::counter!(
+        "diesel_new_inserts_total",
+        "table" => table,
+        "result" => if result.is_ok() { "ok" } else { "error" },
+    )
+    .increment(1);
+}
@@ -0,0 +1,100 @@
+//! Building New structs from loosely-typed JSON
+
+use std::error::Error;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_json::Map;
+
+pub use serde_json::Value;
+
+/// Error from `TryFrom<serde_json::Value>` for a New struct
+#[derive(Debug)]
+pub enum FromJsonError {
+    /// The value wasn't a JSON object
+    NotAnObject,
+    /// A field that isn't an `Option` wasn't in the object
+    Missing {
+        /// Name of the field
+        field: &'static str,
+    },
+    /// A field of the object couldn't be deserialized into the type of the field
+    Field {
+        /// Name of the field
+        field: &'static str,
+        /// Type of the field, as written in the struct
+        expected: &'static str,
+        source: serde_json::Error,
+    },
+}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromJsonError::NotAnObject => f.write_str("expected a JSON object"),
+            FromJsonError::Missing { field } => write!(f, "field `{}` is missing", field),
+            FromJsonError::Field {
+                field,
+                expected,
+                source,
+            } => write!(
+                f,
+                "field `{}` is not a valid `{}`: {}",
+                field, expected, source
+            ),
+        }
+    }
+}
+
+impl Error for FromJsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromJsonError::NotAnObject | FromJsonError::Missing { .. } => None,
+            FromJsonError::Field { source, .. } => Some(source),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn object(value: Value) -> Result<Map<String, Value>, FromJsonError> {
+    match value {
+        Value::Object(object) => Ok(object),
+        _ => Err(FromJsonError::NotAnObject),
+    }
+}
+
+/// Takes a field that has to be in the object out of it
+#[doc(hidden)]
+pub fn required<T: DeserializeOwned>(
+    object: &mut Map<String, Value>,
+    field: &'static str,
+    expected: &'static str,
+) -> Result<T, FromJsonError> {
+    match object.remove(field) {
+        Some(value) => deserialize(value, field, expected),
+        None => Err(FromJsonError::Missing { field }),
+    }
+}
+
+/// Takes an `Option` field out of the object, which is `None` when it is
+/// missing
+#[doc(hidden)]
+pub fn optional<T: DeserializeOwned>(
+    object: &mut Map<String, Value>,
+    field: &'static str,
+    expected: &'static str,
+) -> Result<T, FromJsonError> {
+    deserialize(object.remove(field).unwrap_or(Value::Null), field, expected)
+}
+
+fn deserialize<T: DeserializeOwned>(
+    value: Value,
+    field: &'static str,
+    expected: &'static str,
+) -> Result<T, FromJsonError> {
+    serde_json::from_value(value).map_err(|source| FromJsonError::Field {
+        field,
+        expected,
+        source,
+    })
+}
@@ -1,10 +1,62 @@
 pub use ::macro_rules_attribute::apply;
 pub use ::macro_rules_attribute::derive;
+#[cfg(feature = "async")]
+#[doc(hidden)]
+pub use diesel_async;
 pub use paste::paste;
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
+#[cfg(feature = "wasm")]
+#[doc(hidden)]
+pub use wasm_bindgen;
+
+#[cfg(feature = "proc-macro")]
+pub use diesel_autoincrement_new_struct_derive::new_insertable;
+// The expansion of `#[new_insertable]` and the serde derives of `#[new(event)]`
+// refer to this crate by name
+#[cfg(all(test, any(feature = "proc-macro", feature = "serde")))]
+extern crate self as diesel_autoincrement_new_struct;
+
+#[cfg(feature = "async")]
+pub use async_insert::InsertNewAsync;
+pub use batch::BatchInserter;
+pub use has_new::HasNewInsertable;
+pub use id::AutoincrementId;
+pub use insert::{AnyNewInsertable, InsertNew};
+pub use retry::{is_retryable, with_retry, RetryPolicy};
+pub use table_name::{InstrumentedTable, TableName};
+
+#[cfg(feature = "async")]
+mod async_insert;
+mod batch;
+#[cfg(feature = "dynamic-schema")]
+pub mod dynamic;
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod event;
+#[cfg(feature = "form")]
+pub mod form;
+mod has_new;
+mod id;
+mod insert;
+#[doc(hidden)]
+pub mod instrument;
+#[cfg(feature = "serde_json")]
+pub mod json;
+pub mod raw;
+mod retry;
+pub mod schema;
+mod table_name;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 
 pub mod prelude {
     #[doc(no_inline)]
     pub use crate::apply;
+    #[cfg(feature = "proc-macro")]
+    pub use crate::new_insertable;
+    pub use crate::InsertNew;
     pub use crate::NewInsertable;
 }
 
@@ -50,14 +102,203 @@ pub mod prelude {
 /// //    name: String
 /// // }
 /// ```
+///
+/// With the `proc-macro` feature, `#[new_insertable]` does the same after
+/// checking the struct and its `#[new(...)]` options, with errors that point
+/// at the options that are wrong.
 macro_rules! NewInsertable {( $($item:tt)* ) => (
-    #[$crate::derive($crate::diesel_new!)]
-    $($item)*
+    $crate::diesel_new! { @attrs [entity] [] [] $($item)* }
 )}
 
 /// Macro to generate a New struct for Diesel insertions without an 'id' field
 ///
 /// All struct and field metadata is kept; documentation, serde attributes etc.
+/// The generated struct also gets a `#[doc(alias)]` for the name of the
+/// struct, so that searching for one in rustdoc turns up the other, and a
+/// `SKIPPED_COLUMNS` const with the columns of the table that are left out of
+/// it, such as `["id"]`, for tooling that checks that each of them has a
+/// default in the database.
+///
+/// # Options
+///
+/// The generated struct can be configured with `#[new(...)]` attributes on the
+/// struct and its fields, which are consumed by the macro:
+///
+/// - `#[new(manual_insertable)]`: don't derive `Insertable` on the generated
+///   struct, for when you want to write the `Insertable` impl by hand
+/// - `#[new(derive(...))]`: derive these on the New structs instead of the
+///   derives of the struct, for derives such as `Queryable` that only make
+///   sense for rows. `Insertable` is still derived, and `Identifiable` is left
+///   off the New structs either way
+/// - `#[new(group = "...")]`: also generate a `New{Struct}{Group}` struct with
+///   only the fields marked with the same `#[new(group = "...")]`, which can
+///   be given more than once on both the struct and its fields
+/// - `#[new(name = CreateUser)]`: name the generated struct something other
+///   than `New{Struct}`, for codebases with naming conventions of their own.
+///   Groups and builders are named after it, e.g. `CreateUser{Group}`
+/// - generic parameters and lifetimes on the struct, e.g. `User<'a>`, are
+///   carried onto the New struct and its groups, which then have to use each
+///   of them. Bounds go on the parameters, as Diesel's derives can't handle a
+///   `where` clause, and options that generate methods or other structs
+///   around the New struct, such as `builder`, fail to compile on them
+/// - `#[new(event)]`: also generate a serde-serializable `{Struct}CreatedEvent`
+///   with the id and every field of the New struct, for outbox-style events.
+///   With `#[new(create)]`, `NewStruct::create_with_event(conn)` returns it
+///   along with the row. Requires the `serde` feature
+/// - `#[new(update_from_new)]`: also generate `Struct::update_from_new(id, new, conn)`
+///   for PUT-style updates of every column of a row. The New struct has to be
+///   `AsChangeset`, which it is when the struct derives it
+/// - `#[new(conversions)]`: also generate `From<Struct>` for the New struct,
+///   which drops the id, and `Struct::from_new(id, new)`, for upserts and
+///   duplicating rows. Fields with `#[new(type = ...)]` are converted with
+///   `Into` both ways
+/// - `#[new(check_nullable)]`: fail to compile when a field is an `Option` but
+///   its column isn't `Nullable` in the `table!`, or the other way around. Only
+///   fields whose type is spelled `Option<...>` are treated as optional
+/// - `#[new(builder)]`: also generate `NewStruct::builder()`, a staged builder
+///   where every field that isn't an `Option` has to be set, in order, before
+///   the `Option` fields can be set and the New struct built
+/// - `#[new(update_struct)]`: also generate an `Update{Struct}` changeset with
+///   every field wrapped in an `Option`, where `None` leaves the column as it
+///   is, and `From<Struct>` for it with every field set, for full-row updates
+///   through the same changeset. `Update{Struct}::update(id, conn)` applies it
+///   and returns the row, which is only read when the changeset `is_empty()`,
+///   as Diesel errors on empty changesets. Fields with `#[new(type = ...)]`
+///   keep the type of the struct
+/// - `#[new(treat_none_as_null)]`: on the struct or on an `Option` field, keep
+///   the `Option` fields of `Update{Struct}` as they are, so that `None` sets
+///   the column to `NULL` (and the column is always written). Needs Diesel 2.2
+///   or newer
+/// - `#[new(create)]`: also generate `NewStruct::create(conn)`, which inserts
+///   the row and returns it as the struct with a `RETURNING` clause, so that
+///   the type of the row doesn't have to be spelled out at each call site. It
+///   only compiles for backends with support for `RETURNING`
+/// - `#[new(retry)]`: also generate `NewStruct::create_with_retry(conn, policy)`,
+///   which inserts in a transaction of its own and tries again on serialization
+///   failures and deadlocks, see [`with_retry`]
+/// - `#[new(diff)]`: with `update_struct`, also generate
+///   `Update{Struct}::diff(before, after)`, which only sets the fields that
+///   differ between two snapshots of a row. The fields need `PartialEq` and
+///   `Clone`
+/// - `#[new(cqrs)]`: also generate a `{Struct}ReadModel` with every field that
+///   only derives `Queryable` and `Selectable`, and a `{Struct}WriteModel`
+///   without the id that only derives `Insertable`. Only docs and
+///   `#[diesel(...)]` attributes are copied onto them
+/// - `#[new(cfg(...))]`: only generate the New structs and everything else
+///   when the predicate holds, such as `cfg(feature = "writes")` for crates
+///   that are also built without a write path. A `#[cfg(...)]` on the struct
+///   itself is carried over the same way, and both can be combined
+/// - `#[new(check_for_backend(...))]`: check at compile time that the New
+///   struct can be inserted with each of the given backends, such as
+///   `check_for_backend(diesel::pg::Pg, diesel::sqlite::Sqlite)`
+/// - `#[new(raw_insert)]`: also generate `NewStruct::raw_insert_sql::<DB>()`,
+///   the insert as raw SQL with placeholders, and `bind_raw_insert(query)` to
+///   bind the fields to a `sql_query` made from it
+/// - `#[new(from_form)]`: also implement `TryFrom<&HashMap<String, String>>` for
+///   the New struct, parsing each field with `FromStr` and reporting every
+///   field that failed at once. Needs the `form` feature
+/// - `#[new(dynamic)]`: also generate `NewStruct::insert_into_dynamic(name)`,
+///   which inserts into a table that is only known by name at runtime, with
+///   the columns of the struct's table. Needs the `dynamic-schema` feature
+/// - `#[new(from_json)]`: also implement `TryFrom<serde_json::Value>` for the
+///   New struct, with errors saying which field was missing or failed and
+///   which type it was expected to be. Keys are the names of the fields, as
+///   `#[serde(rename)]` isn't looked at. Requires the `serde_json` feature
+/// - `#[new(verify_schema)]`: also generate `NewStruct::verify_schema(conn)`,
+///   which checks at runtime that every column of the New struct is in the
+///   table in the database, and is nullable exactly when its field is an
+///   `Option`, for a startup check that catches migrations lagging behind the
+///   code. The backend needs the `postgres`, `mysql` or `sqlite` feature. See
+///   [`schema::SchemaMismatch`] for what it reports
+/// - `#[new(check_id)]`: fail to compile when the id isn't an integer, which is
+///   the only kind of id generated by the database. Newtypes around integer ids
+///   can implement [AutoincrementId] to pass the check
+/// - `#[new(proptest)]`: also generate a test that inserts random New structs
+///   into an in-memory SQLite table created from the `table!`, and checks
+///   that every field reads back the same. Requires the `test-support`
+///   feature, and every field type to implement `proptest::arbitrary::Arbitrary`
+/// - `#[new(rename(method = name, ...))]`: give generated methods other names,
+///   to follow naming guidelines or to avoid clashing with methods of the
+///   struct. `builder`, `update_from_new`, `create`, `create_with_retry`,
+///   `import`, `diff` and `from_new` can be renamed
+/// - `#[new(with_id)]`: also generate a `{Struct}WithId` with every field and
+///   the id, with the same attributes as the struct other than `Identifiable`,
+///   and `{Struct}WithId::import(conn)` to insert it as it is for imports that have
+///   to keep the ids of the rows
+/// - `#[new(no_id)]`: for tables without an `id`, such as join tables, keep
+///   every field in the New struct so that the table still has one. Options
+///   that need the id, such as `event` and `update_from_new`, can't be used
+/// - `#[new(skip)]` on a field: leave it out of the New structs, for columns
+///   such as `created_at` that are filled in by a default or a trigger. These
+///   are listed in `SKIPPED_COLUMNS` along with the id
+/// - `#[new(default)]` or `#[new(default = expr)]` on a field: leave it out of
+///   the New structs, but insert it with `Default::default()` or the
+///   expression, for columns such as a status that always start out the same.
+///   `from_new` fills it in the same way. The New structs then implement
+///   `Insertable` themselves, so they can only be inserted by value
+/// - `#[new(primary_key)]` on a field: strip that field instead of a first
+///   field named `id`, for keys such as `user_id` and for legacy tables where
+///   the key isn't the first column. `with_id` and `cqrs` still need it to be
+///   the first field
+/// - `#[new(attr(...))]`: add an attribute to the New structs only, such as
+///   `attr(derive(bincode::Encode, bincode::Decode))` for queueing them, or
+///   `attr(cfg_attr(feature = "queue", derive(...)))` to only do so with a
+///   feature of your own. Can be given more than once
+/// - `#[new(wasm)]`: put `#[wasm_bindgen(getter_with_clone)]` on the New
+///   structs, but not on the struct itself, to share them with a WASM
+///   frontend. Requires the `wasm` feature
+/// - `#[new(graphql(pool = Pool))]`: also generate a `{Struct}Mutation` for
+///   [async-graphql](https://docs.rs/async-graphql), with a `create_{struct}`
+///   resolver that takes the New struct as its input, gets a connection from
+///   the `Pool` in the context of the request (a diesel-async `deadpool` or
+///   `bb8` pool) and returns the inserted row. The New struct has to be an
+///   `InputObject`, such as with `#[new(attr(derive(async_graphql::InputObject)))]`,
+///   and the struct an output type. Requires the `async` feature
+/// - `#[new(allow(...))]`: allow lints on everything generated from the struct
+///   (but not on the struct itself), such as `allow(clippy::struct_excessive_bools)`
+///   for workspaces that deny lints that the generated code can't avoid. Can be
+///   given more than once
+/// - `#[new(display)]`: also implement `Display` for the New struct, printing
+///   `table(field=value, ...)` with the `Debug` of each value for logs. Fields
+///   marked with `#[new(redact)]` are printed as `***`
+/// - `#[new(diesel(...))]` on a field: add a `#[diesel(...)]` to the field of
+///   the generated struct only, such as a `serialize_as` for inserting a custom
+///   type. `#[diesel(...)]` attributes on the field itself, including
+///   `sql_type` and the ones only used by `Queryable`, are copied as usual
+/// - `#[new(type = ...)]` on a field: use another type for the field of the
+///   generated struct, such as a validated newtype around the `String` of the
+///   struct, so that invalid values can't be inserted. It is inserted as the
+///   type of the struct with `#[diesel(serialize_as = ...)]`, which needs
+///   `From<NewType> for Type`
+/// - `#[new(pg_ty(...) = ..., sqlite_ty(...) = ...)]` on a field: use another
+///   type for the field of the generated struct when the `cfg` predicate in
+///   the parentheses holds, such as `pg_ty(feature = "postgres") = uuid::Uuid`,
+///   for models that are shared between backends. The predicates are those of
+///   the crate with the struct, the first one that holds wins, and the value
+///   is inserted as it is. The type is picked by a hidden type alias next to
+///   the struct, `__DieselNew{Struct}{Field}`, which is the type of the field
+///   in the docs and whose name is reserved
+/// - `#[new(example = "...")]` on a field: add an example to the field of the
+///   generated struct for the schema derive copied onto it, as utoipa's
+///   `#[schema(example = ...)]`, or schemars' `#[schemars(example = ...)]` when
+///   the struct is `#[new(example_attr = schemars)]`
+/// - `#[new(immutable)]` on a field: keep it in the New structs, but leave it
+///   out of `Update{Struct}` and `diff`, and out of the changeset of the New
+///   structs with `#[diesel(skip_update)]`, for columns such as a slug or an
+///   owner that are only ever set by the insert. Needs Diesel 2.2 or newer
+/// - `#[new(natural_key)]` on fields: generate `natural_key(&self)` on both the
+///   struct and the New struct, returning a tuple of references to the marked
+///   fields, and `New{Struct}::natural_key_columns()` with their columns, such
+///   as for the conflict target of an upsert
+/// - `#[new(drop_deprecated)]` on a field: don't copy its `#[deprecated]` onto
+///   the generated struct. By default it is copied, so that new inserts of a
+///   deprecated field warn
+/// - `#[new(doc = "...")]`: replace the docs copied from the struct with the
+///   given line, which can be given more than once. String literals and
+///   `{Entity}`, for the name of the struct, are concatenated, e.g.
+///   `#[new(doc = "Fields for inserting a new " {Entity})]`
+/// - `#[new(append_doc = "...")]`: like `doc`, but adds the line after the
+///   copied docs instead of replacing them
 ///
 /// # Example
 ///
@@ -97,78 +338,5419 @@ macro_rules! NewInsertable {( $($item:tt)* ) => (
 /// ```
 #[macro_export]
 macro_rules! diesel_new {
-    (
-        $(#[$struct_meta:meta])*
-        $struct_vis:vis struct $StructName:ident {
-            // We wanna make sure we don't catch the ID struct in the repetition
-            $(#[$_id_meta:meta])*
-            $_id_field_vis:vis id : $_id_type:ty,
-            // Here is the repetition for every field except the ID field
-            $(
-                $(#[$field_meta:meta])*
-                $field_vis:vis $field_name:ident : $field_ty:ty
-            ),* $(,)?
-        }
-    ) => (
-        $crate::paste! {
-            $(#[$struct_meta])*
-            #[derive(diesel::Insertable)]
-            $struct_vis struct [< New $StructName >] {
+    // Struct-level `#[new(...)]` attributes configure the macro and are never
+    // copied onto either struct
+    (@attrs $mode:tt [$($opts:tt)*] $meta:tt #[new($($opt:tt)*)] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)*] $meta [$($opt)*] $($rest)* }
+    );
+    // `#[diesel(...)]` attributes are copied as usual, but we also need to know
+    // the table for generating anything other than the struct itself
+    (@attrs $mode:tt $opts:tt [$($meta:tt)*] #[diesel($($diesel:tt)*)] $($rest:tt)*) => (
+        $crate::diesel_new! { @diesel $mode $opts [$($meta)* [diesel($($diesel)*)]] [$($diesel)*] $($rest)* }
+    );
+    // `#[cfg(...)]` has to hold for anything generated from the struct too, which
+    // is the same as `#[new(cfg(...))]`
+    (@attrs $mode:tt [[cfg [$($cfg:tt)*]] $($opts:tt)*] [$($meta:tt)*] #[cfg $pred:tt] $($rest:tt)*) => (
+        $crate::diesel_new! { @attrs $mode [[cfg [$($cfg)* $pred]] $($opts)*] [$($meta)* [cfg $pred]] $($rest)* }
+    );
+    (@attrs $mode:tt [$($opts:tt)*] [$($meta:tt)*] #[cfg $pred:tt] $($rest:tt)*) => (
+        $crate::diesel_new! { @attrs $mode [[cfg [$pred]] $($opts)*] [$($meta)* [cfg $pred]] $($rest)* }
+    );
+    (@attrs $mode:tt $opts:tt [$($meta:tt)*] #[$($attr:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @attrs $mode $opts [$($meta)* [$($attr)*]] $($rest)* }
+    );
+    // Without an id, every field is already accounted for, which saves looking
+    // for it
+    (@attrs [$mode:ident no_id] $opts:tt $meta:tt $struct_vis:vis struct $StructName:ident { $($body:tt)* }) => (
+        $crate::diesel_new! { @fields [[$mode] $opts $meta [$struct_vis] $StructName [] []] [no_id] [] [] [] $($body)* }
+    );
+    (@attrs $mode:tt $opts:tt $meta:tt $struct_vis:vis struct $StructName:ident { $($body:tt)* }) => (
+        $crate::diesel_new! { @fields [$mode $opts $meta [$struct_vis] $StructName [] []] [] [] [] [] $($body)* }
+    );
+    // The generic parameters are taken up to the `>` that closes them, keeping
+    // their bounds as they are along with the name of each parameter, for the
+    // impls of the generated structs. The `<`s of the bounds are counted in
+    // `$depth`, as `>` and `>>` are the only way to tell where they end
+    (@attrs $mode:tt $opts:tt $meta:tt $struct_vis:vis struct $StructName:ident < $($rest:tt)*) => (
+        $crate::diesel_new! { @generics [$mode $opts $meta [$struct_vis] $StructName] [] [] [] [name] $($rest)* }
+    );
+
+    (@generics $state:tt [$($generics:tt)*] $names:tt [] $_next:tt , $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* ,] $names [] [name] $($rest)* }
+    );
+    (@generics [[$mode:ident no_id] $($state:tt)*] $generics:tt $names:tt [] $_next:tt > { $($body:tt)* }) => (
+        $crate::diesel_new! { @fields [[$mode] $($state)* [$generics $names] []] [no_id] [] [] [] $($body)* }
+    );
+    (@generics [$($state:tt)*] $generics:tt $names:tt [] $_next:tt > { $($body:tt)* }) => (
+        $crate::diesel_new! { @fields [$($state)* [$generics $names] []] [] [] [] [] $($body)* }
+    );
+    // Diesel's derives can't take a `where` clause
+    (@generics $state:tt $generics:tt $names:tt [] $_next:tt > where $($_rest:tt)*) => (
+        ::core::compile_error!("expected the bounds of the generic parameters to be on the parameters, instead of in a `where` clause");
+    );
+    (@generics $state:tt [$($generics:tt)*] [$($names:tt)*] [] [name] const $name:ident $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* const $name] [$($names)* $name] [] [bounds] $($rest)* }
+    );
+    (@generics $state:tt [$($generics:tt)*] [$($names:tt)*] [] [name] $name:tt $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* $name] [$($names)* $name] [] [bounds] $($rest)* }
+    );
+    (@generics $state:tt [$($generics:tt)*] $names:tt [$($depth:tt)*] $next:tt < $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* <] $names [< $($depth)*] $next $($rest)* }
+    );
+    (@generics $state:tt [$($generics:tt)*] $names:tt [< $($depth:tt)*] $next:tt > $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* >] $names [$($depth)*] $next $($rest)* }
+    );
+    (@generics $state:tt [$($generics:tt)*] $names:tt [< < $($depth:tt)*] $next:tt >> $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* >>] $names [$($depth)*] $next $($rest)* }
+    );
+    (@generics $state:tt [$($generics:tt)*] $names:tt [<] $next:tt >> $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* >] $names [] $next > $($rest)* }
+    );
+    (@generics $state:tt [$($generics:tt)*] $names:tt $depth:tt $next:tt $token:tt $($rest:tt)*) => (
+        $crate::diesel_new! { @generics $state [$($generics)* $token] $names $depth $next $($rest)* }
+    );
+
+    (@diesel $mode:tt [$($opts:tt)*] $meta:tt [table_name = $($table:ident)::+ $(, $($_diesel:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @attrs $mode [$($opts)* [table_name $($table)::+]] $meta $($rest)* }
+    );
+    (@diesel $mode:tt $opts:tt $meta:tt [$_diesel:tt $($diesel:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @diesel $mode $opts $meta [$($diesel)*] $($rest)* }
+    );
+    (@diesel $mode:tt $opts:tt $meta:tt [] $($rest:tt)*) => (
+        $crate::diesel_new! { @attrs $mode $opts $meta $($rest)* }
+    );
+
+    // `#[new(no_id)]` is carried along with the mode, as it changes how the
+    // fields are parsed
+    (@opts [$mode:ident $($_no_id:ident)?] $opts:tt $meta:tt [no_id $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts [$mode no_id] $opts $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [manual_insertable $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [manual_insertable]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [check_nullable $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [check_nullable]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [update_from_new $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [update_from_new]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [conversions $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [conversions]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [update_struct $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [update_struct]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [cqrs $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [cqrs]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [treat_none_as_null $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [treat_none_as_null]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [create $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [create]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [retry $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [retry]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [diff $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [diff]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [raw_insert $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [raw_insert]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [from_form $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [from_form]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [derive($($derive:tt)*) $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [derive [$($derive)*]]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [attr($($attr:tt)*) $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [attr [$($attr)*]]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt $opts:tt $meta:tt [rename($($renames:tt)*) $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @rename $mode $opts $meta [$($renames)*] [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [with_id $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [with_id]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [wasm $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [wasm]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [proptest $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [proptest]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [allow $lints:tt $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [allow $lints]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [graphql(pool = $pool:ty) $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [graphql $pool]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [verify_schema $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [verify_schema]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [dynamic $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [dynamic]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [from_json $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [from_json]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [builder $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [builder]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [check_id $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [check_id]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [display $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [display]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [event $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [event]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [[cfg [$($cfgs:tt)*]] $($opts:tt)*] $meta:tt [cfg $cfg:tt $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [[cfg [$($cfgs)* $cfg]] $($opts)*] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [cfg $cfg:tt $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [[cfg [$cfg]] $($opts)*] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [check_for_backend($($backend:path),+ $(,)?) $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [check_for_backend $($backend),+]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [example_attr = $example_attr:ident $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [example_attr $example_attr]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [group = $group:literal $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [group $group]] $meta [$($($opt)*)?] $($rest)* }
+    );
+    // `#[new(name = ...)]` always comes right after `[cfg]`, which is always
+    // first, so that neither has to be looked up
+    (@opts $mode:tt [[name $_name:ident] $($_opts:tt)*] $meta:tt [name = $($opt:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!("`#[new(name = ...)]` can only be given once");
+    );
+    (@opts $mode:tt [[cfg $cfg:tt] [name $_name:ident] $($_opts:tt)*] $meta:tt [name = $($opt:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!("`#[new(name = ...)]` can only be given once");
+    );
+    (@opts $mode:tt [[cfg $cfg:tt] $($opts:tt)*] $meta:tt [name = $name:ident $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [[cfg $cfg] [name $name] $($opts)*] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt [$($opts:tt)*] $meta:tt [name = $name:ident $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [[name $name] $($opts)*] $meta [$($($opt)*)?] $($rest)* }
+    );
+    (@opts $mode:tt $opts:tt $meta:tt [doc = $($opt:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @doc_opt $mode $opts $meta [doc] [] [$($opt)*] $($rest)* }
+    );
+    (@opts $mode:tt $opts:tt $meta:tt [append_doc = $($opt:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @doc_opt $mode $opts $meta [append_doc] [] [$($opt)*] $($rest)* }
+    );
+    (@opts $mode:tt $opts:tt $meta:tt [] $($rest:tt)*) => (
+        $crate::diesel_new! { @attrs $mode $opts $meta $($rest)* }
+    );
+    (@opts $mode:tt $opts:tt $meta:tt [$($opt:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!(::core::concat!(
+            "unknown `#[new]` option: `", ::core::stringify!($($opt)*), "`"
+        ));
+    );
+
+    // A doc line is made of string literals and `{Entity}`, so it runs until the next comma
+    (@doc_opt $mode:tt [$($opts:tt)*] $meta:tt [$kind:ident] $line:tt [, $($opt:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [$kind $line]] $meta [$($opt)*] $($rest)* }
+    );
+    (@doc_opt $mode:tt [$($opts:tt)*] $meta:tt [$kind:ident] $line:tt [] $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode [$($opts)* [$kind $line]] $meta [] $($rest)* }
+    );
+    (@doc_opt $mode:tt $opts:tt $meta:tt $kind:tt [$($line:tt)*] [$part:tt $($opt:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @doc_opt $mode $opts $meta $kind [$($line)* $part] [$($opt)*] $($rest)* }
+    );
+
+    // Each method in `#[new(rename(...))]` is checked against the methods that
+    // can be renamed, so that a typo doesn't go unnoticed
+    (@rename $mode:tt $opts:tt $meta:tt [] $opt:tt $($rest:tt)*) => (
+        $crate::diesel_new! { @opts $mode $opts $meta $opt $($rest)* }
+    );
+    (@rename $mode:tt [$($opts:tt)*] $meta:tt [$method:ident = $name:ident $(, $($renames:tt)*)?] $opt:tt $($rest:tt)*) => (
+        $crate::__diesel_new_renamable! { $method
+            [$crate::diesel_new] { @rename $mode [$($opts)* [rename $method $name]] $meta [$($($renames)*)?] $opt $($rest)* }
+        }
+    );
+    (@rename $mode:tt $opts:tt $meta:tt [$($renames:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!(::core::concat!(
+            "expected `method = name` in `#[new(rename(...))]`, found: `", ::core::stringify!($($renames)*), "`"
+        ));
+    );
+
+    // Field-level `#[new(...)]` attributes are split out in the same way
+    (@fields $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt #[new($($opt:tt)*)] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields $field_opts $field_meta [$($opt)*] $($rest)* }
+    );
+    (@fields $state:tt $id:tt $fields:tt $field_opts:tt [$($field_meta:tt)*] #[diesel($($diesel:tt)*)] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_diesel $state $id $fields $field_opts [$($field_meta)* #[diesel($($diesel)*)]] [$($diesel)*] $($rest)* }
+    );
+    // Each step of a macro counts towards the recursion limit, so the docs of a
+    // field (other than the ID field) are taken along with the rest of it to
+    // keep wide structs working
+    (@fields $state:tt [$($id:tt)+] $fields:tt $field_opts:tt [$($field_meta:tt)*] $(#[doc $($doc:tt)*])+
+        $field_vis:vis $field_name:ident : $ty_start:tt $($rest:tt)*
+    ) => (
+        $crate::diesel_new! { @field [$ty_start] $state [$($id)+] $fields $field_opts [$($field_meta)* $(#[doc $($doc)*])+]
+            [$field_vis] $field_name : $ty_start $($rest)*
+        }
+    );
+    (@fields $state:tt $id:tt $fields:tt $field_opts:tt [$($field_meta:tt)*] #[$($attr:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @fields $state $id $fields $field_opts [$($field_meta)* #[$($attr)*]] $($rest)* }
+    );
+    // We wanna make sure we don't catch the ID field along with the others. The
+    // struct keeps it where it is, so it goes along with the rest of its fields
+    (@fields [$mode:tt $opts:tt $meta:tt $struct_vis:tt $StructName:ident $generics:tt []] [] []
+        $id_opts:tt $id_meta:tt $id_field_vis:vis id : $id_type:ty $(, $($rest:tt)*)?
+    ) => (
+        $crate::diesel_new! {
+            @fields [$mode $opts $meta $struct_vis $StructName $generics [[$id_meta [$id_field_vis] id [$id_type]]]]
+                [$id_opts $id_meta [$id_field_vis] id [$id_type]] [] [] [] $($($rest)*)?
+        }
+    );
+    (@fields $state:tt [] $fields:tt [] []) => (
+        ::core::compile_error!(
+            "expected the first field of the struct to be `id`, a field with `#[new(primary_key)]`, or `#[new(no_id)]` on a struct without one"
+        );
+    );
+    (@fields [$mode:tt $opts:tt $meta:tt $struct_vis:tt $StructName:ident $generics:tt $entity_fields:tt] $id:tt $fields:tt [] []) => (
+        $crate::diesel_new! { @generate $mode $opts $meta $struct_vis $StructName $generics $id $fields $entity_fields }
+    );
+    // Here is every field except the ID field, with `Option` fields marked so
+    // that they can be checked against `Nullable` columns
+    (@fields $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt
+        $field_vis:vis $field_name:ident : $ty_start:tt $($rest:tt)*
+    ) => (
+        $crate::diesel_new! { @field [$ty_start] $state $id $fields $field_opts $field_meta
+            [$field_vis] $field_name : $ty_start $($rest)*
+        }
+    );
+    // The first token of the type is passed along as is, as matching `Option`
+    // directly would change how the type is stringified
+    (@field [Option] [$mode:tt $opts:tt $meta:tt $struct_vis:tt $StructName:ident $generics:tt [$($entity_fields:tt)*]] $id:tt [$($fields:tt)*]
+        [] $field_meta:tt $field_vis:tt $field_name:ident : $field_ty:ty $(, $($rest:tt)*)?
+    ) => (
+        $crate::diesel_new! {
+            @fields [$mode $opts $meta $struct_vis $StructName $generics [$($entity_fields)* [$field_meta $field_vis $field_name [$field_ty]]]]
+                $id [$($fields)* [[[nullable]] $field_meta $field_vis $field_name [$field_ty]]] [] []
+                $($($rest)*)?
+        }
+    );
+    (@field [Option] $state:tt $id:tt $fields:tt [$($field_opts:tt)+] $($rest:tt)*) => (
+        $crate::diesel_new! { @field [] $state $id $fields [[nullable] $($field_opts)*] $($rest)* }
+    );
+    // The struct keeps the attributes of its fields as they are, but they can
+    // differ on the New structs, so each field is kept twice
+    (@field [$($_ty_start:tt)?] [$mode:tt $opts:tt $meta:tt $struct_vis:tt $StructName:ident $generics:tt [$($entity_fields:tt)*]] $id:tt [$($fields:tt)*]
+        [] $field_meta:tt $field_vis:tt $field_name:ident : $field_ty:ty $(, $($rest:tt)*)?
+    ) => (
+        $crate::diesel_new! {
+            @fields [$mode $opts $meta $struct_vis $StructName $generics [$($entity_fields)* [$field_meta $field_vis $field_name [$field_ty]]]]
+                $id [$($fields)* [[] $field_meta $field_vis $field_name [$field_ty]]] [] []
+                $($($rest)*)?
+        }
+    );
+    (@field [$($_ty_start:tt)?] [$mode:tt $opts:tt $meta:tt $struct_vis:tt $StructName:ident $generics:tt [$($entity_fields:tt)*]] $id:tt $fields:tt
+        $field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident : $field_ty:ty $(, $($rest:tt)*)?
+    ) => (
+        $crate::diesel_new! { @new_field_meta [] [] $field_opts $field_meta
+            [[$mode $opts $meta $struct_vis $StructName $generics [$($entity_fields)* [$field_meta $field_vis $field_name [$field_ty]]]]
+                $id $fields [$field_opts $field_vis $field_name [$field_ty]]]
+            $($($rest)*)?
+        }
+    );
+
+    // `#[new(diesel(...))]` adds a `#[diesel(...)]` to the field of the New
+    // structs only, for when inserting a custom type needs a `serialize_as`
+    // etc. that the struct doesn't.
+    //
+    // `#[deprecated]` is copied onto the New structs, so that new inserts of a
+    // deprecated field warn, unless the field is `#[new(drop_deprecated)]`
+    (@new_field_meta [$($added:tt)*] $drop:tt [[diesel $diesel:tt] $($opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @new_field_meta [$($added)* #[diesel $diesel]] $drop [$($opts)*] $($rest)* }
+    );
+    // `#[new(example = ...)]` is for the schema derive on the New structs, which
+    // is utoipa's `#[schema(...)]` unless the struct is `#[new(example_attr = schemars)]`
+    (@new_field_meta $added:tt $drop:tt [[example $example:literal] $($opts:tt)*]
+        $field_meta:tt [[$mode:tt $struct_opts:tt $($state:tt)*] $($k:tt)*] $($rest:tt)*
+    ) => (
+        $crate::__diesel_new_value! { example_attr $struct_opts [utoipa]
+            [$crate::diesel_new] { @new_field_example $example $added $drop [$($opts)*]
+                [$field_meta [[$mode $struct_opts $($state)*] $($k)*] $($rest)*]
+            }
+        }
+    );
+    (@new_field_example $example:literal [$($added:tt)*] $drop:tt $opts:tt [$($rest:tt)*] [utoipa]) => (
+        $crate::diesel_new! { @new_field_meta [$($added)* #[schema(example = $example)]] $drop $opts $($rest)* }
+    );
+    (@new_field_example $example:literal [$($added:tt)*] $drop:tt $opts:tt [$($rest:tt)*] [schemars]) => (
+        $crate::diesel_new! { @new_field_meta [$($added)* #[schemars(example = &$example)]] $drop $opts $($rest)* }
+    );
+    (@new_field_example $example:literal $added:tt $drop:tt $opts:tt $rest:tt [$($example_attr:tt)*]) => (
+        ::core::compile_error!(::core::concat!(
+            "expected `example_attr` to be `utoipa` or `schemars`, found `", ::core::stringify!($($example_attr)*), "`"
+        ));
+    );
+    // `#[new(type = ...)]` swaps the type of the field on the New structs for
+    // e.g. a validated newtype, which is inserted as the type of the struct.
+    // The type of the struct is kept as `[entity_ty]` for the natural key
+    (@new_field_meta [$($added:tt)*] $drop:tt [[ty $ty:ty] $($opts:tt)*]
+        $field_meta:tt [$state:tt $id:tt $fields:tt [[$($field_opts:tt)*] $field_vis:tt $field_name:ident [$field_ty:ty]]] $($rest:tt)*
+    ) => (
+        $crate::diesel_new! { @new_field_meta [$($added)* #[diesel(serialize_as = $field_ty)]] $drop [$($opts)*]
+            $field_meta [$state $id $fields [[$($field_opts)* [entity_ty $field_ty]] $field_vis $field_name [$ty]]] $($rest)*
+        }
+    );
+    // `#[new(pg_ty(...) = ...)]` and `#[new(sqlite_ty(...) = ...)]` swap the
+    // type of the field on the New structs for a type alias, which is defined
+    // once for each predicate with `#[cfg(...)]`, so that the predicates are
+    // those of the crate with the struct rather than features of this one.
+    // Unlike `type`, the value is inserted as it is
+    (@new_field_meta $added:tt $drop:tt [[backend_ty $backend:tt] $($opts:tt)*] $field_meta:tt
+        [[$mode:tt $struct_opts:tt $meta:tt $struct_vis:tt $StructName:ident $($state:tt)*] $id:tt $fields:tt
+            [[$($field_opts:tt)*] $field_vis:tt $field_name:ident [$field_ty:ty]]]
+        $($rest:tt)*
+    ) => (
+        $crate::paste! {
+            $crate::__diesel_new_backend_ty! { [< __DieselNew $StructName $field_name:camel >] [$field_ty] [] $backend }
+
+            $crate::diesel_new! { @new_field_meta $added $drop [$($opts)*] $field_meta
+                [[$mode $struct_opts $meta $struct_vis $StructName $($state)*] $id $fields
+                    [[$($field_opts)* [entity_ty $field_ty]] $field_vis $field_name [[< __DieselNew $StructName $field_name:camel >]]]]
+                $($rest)*
+            }
+        }
+    );
+    // `#[new(skip)]` fields are filled in by the database, so they are only kept
+    // on the struct itself, and listed in the options for `SKIPPED_COLUMNS`
+    (@new_field_meta $added:tt $drop:tt [[skip] $($_opts:tt)*] $field_meta:tt
+        [[$mode:tt [$($opts:tt)*] $($state:tt)*] $id:tt $fields:tt [$field_opts:tt $field_vis:tt $field_name:ident $field_ty:tt]]
+        $($rest:tt)*
+    ) => (
+        $crate::diesel_new! {
+            @fields [$mode [$($opts)* [reordered] [skip $field_opts $field_name]] $($state)*] $id $fields [] []
+                $($rest)*
+        }
+    );
+    // `#[new(default)]` fields are left out in the same way, but are inserted
+    // with their value by the `Insertable` impl of the New structs
+    (@new_field_meta $added:tt $drop:tt [[default $value:tt] $($_opts:tt)*] $field_meta:tt
+        [[$mode:tt [$($opts:tt)*] $($state:tt)*] $id:tt $fields:tt [$field_opts:tt $field_vis:tt $field_name:ident $field_ty:tt]]
+        $($rest:tt)*
+    ) => (
+        $crate::diesel_new! {
+            @fields [$mode [$($opts)* [reordered] [default $field_meta $field_name $field_ty $value]] $($state)*] $id $fields [] []
+                $($rest)*
+        }
+    );
+    // `#[new(immutable)]` fields are set by the insert and never again, so they
+    // are left out of the changeset when the New structs are `AsChangeset`
+    (@new_field_meta [$($added:tt)*] $drop:tt [[immutable] $($opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @new_field_meta [$($added)* #[diesel(skip_update)]] $drop [$($opts)*] $($rest)* }
+    );
+    (@new_field_meta $added:tt $_drop:tt [[drop_deprecated] $($opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @new_field_meta $added [drop] [$($opts)*] $($rest)* }
+    );
+    (@new_field_meta $added:tt $drop:tt [$_opt:tt $($opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @new_field_meta $added $drop [$($opts)*] $($rest)* }
+    );
+    (@new_field_meta [$($added:tt)*] [] [] [$($field_meta:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @new_field [$($field_meta)* $($added)*] $($rest)* }
+    );
+    (@new_field_meta [$($added:tt)*] [drop] [] $field_meta:tt $($rest:tt)*) => (
+        $crate::diesel_new! { @undeprecated [] $field_meta [$($added)*] $($rest)* }
+    );
+    (@undeprecated $kept:tt [#[deprecated $($_deprecated:tt)*] $($field_meta:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @undeprecated $kept [$($field_meta)*] $($rest)* }
+    );
+    (@undeprecated [$($kept:tt)*] [#$attr:tt $($field_meta:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @undeprecated [$($kept)* #$attr] [$($field_meta)*] $($rest)* }
+    );
+    (@undeprecated [$($kept:tt)*] [] [$($added:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @new_field [$($kept)* $($added)*] $($rest)* }
+    );
+    // The field after a `#[new(primary_key)]` is the id. Other than when it is
+    // the first field, the struct is marked as `[reordered]`, as the structs
+    // that have the id as well can't keep it where it is
+    (@new_field $field_meta:tt [$state:tt [primary_key] [] [$field_opts:tt $($field:tt)*]] $($rest:tt)*) => (
+        $crate::diesel_new! { @fields $state [$field_opts $field_meta $($field)*] [] [] [] $($rest)* }
+    );
+    (@new_field $field_meta:tt
+        [[$mode:tt [$($opts:tt)*] $($state:tt)*] [primary_key] $fields:tt [$field_opts:tt $($field:tt)*]] $($rest:tt)*
+    ) => (
+        $crate::diesel_new! {
+            @fields [$mode [$($opts)* [reordered]] $($state)*] [$field_opts $field_meta $($field)*] $fields [] []
+                $($rest)*
+        }
+    );
+    (@new_field $field_meta:tt [$state:tt $id:tt [$($fields:tt)*] [$field_opts:tt $($field:tt)*]] $($rest:tt)*) => (
+        $crate::diesel_new! { @fields $state $id [$($fields)* [$field_opts $field_meta $($field)*]] [] [] $($rest)* }
+    );
+
+    (@field_diesel $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [column_name = $column:ident $(, $($_diesel:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @fields $state $id $fields [[column_name $column] $($field_opts)*] $field_meta $($rest)* }
+    );
+    (@field_diesel $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [$_diesel:tt $($diesel:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_diesel $state $id $fields $field_opts $field_meta [$($diesel)*] $($rest)* }
+    );
+    (@field_diesel $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [] $($rest:tt)*) => (
+        $crate::diesel_new! { @fields $state $id $fields $field_opts $field_meta $($rest)* }
+    );
+
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [group = $group:literal $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [group $group]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [diesel $diesel:tt $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [diesel $diesel]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [drop_deprecated $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [drop_deprecated]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [treat_none_as_null $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [treat_none_as_null]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [type = $ty:ty $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [ty $ty]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    // The types for each backend are kept together at the front of the options
+    // of the field, in the order they were given
+    (@field_opts $state:tt $id:tt $fields:tt [[backend_ty [$($backend:tt)*]] $($field_opts:tt)*] $field_meta:tt
+        [pg_ty($($cfg:tt)*) = $ty:ty $(, $($opt:tt)*)?] $($rest:tt)*
+    ) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [[backend_ty [$($backend)* [($($cfg)*) [$ty]]]] $($field_opts)*] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [pg_ty($($cfg:tt)*) = $ty:ty $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [[backend_ty [[($($cfg)*) [$ty]]]] $($field_opts)*] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [[backend_ty [$($backend:tt)*]] $($field_opts:tt)*] $field_meta:tt
+        [sqlite_ty($($cfg:tt)*) = $ty:ty $(, $($opt:tt)*)?] $($rest:tt)*
+    ) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [[backend_ty [$($backend)* [($($cfg)*) [$ty]]]] $($field_opts)*] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [sqlite_ty($($cfg:tt)*) = $ty:ty $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [[backend_ty [[($($cfg)*) [$ty]]]] $($field_opts)*] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [pg_ty = $($_rest:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!(
+            "expected a `cfg` predicate for `pg_ty`, such as `#[new(pg_ty(feature = \"postgres\") = ...)]`"
+        );
+    );
+    (@field_opts $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [sqlite_ty = $($_rest:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!(
+            "expected a `cfg` predicate for `sqlite_ty`, such as `#[new(sqlite_ty(feature = \"sqlite\") = ...)]`"
+        );
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [example = $example:literal $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [example $example]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [immutable $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [immutable]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [redact $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [redact]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    // `#[new(primary_key)]` leaves the id to be taken from the next field to be
+    // done, wherever it is in the struct
+    (@field_opts $state:tt [] $fields:tt [$($field_opts:tt)*] $field_meta:tt [primary_key $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state [primary_key] $fields [$($field_opts)* [primary_key]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt [no_id] $fields:tt $field_opts:tt $field_meta:tt [primary_key $($_opt:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!("`#[new(primary_key)]` can't be used with `#[new(no_id)]`");
+    );
+    (@field_opts $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [primary_key $($_opt:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!(
+            "`#[new(primary_key)]` can only be on one field, and a first field named `id` is always the primary key"
+        );
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [skip $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [skip]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [default = $value:expr $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [default [$value]]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [default $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [default [::core::default::Default::default()]]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt [$($field_opts:tt)*] $field_meta:tt [natural_key $(, $($opt:tt)*)?] $($rest:tt)*) => (
+        $crate::diesel_new! { @field_opts $state $id $fields [$($field_opts)* [natural_key]] $field_meta [$($($opt)*)?] $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [] $($rest:tt)*) => (
+        $crate::diesel_new! { @fields $state $id $fields $field_opts $field_meta $($rest)* }
+    );
+    (@field_opts $state:tt $id:tt $fields:tt $field_opts:tt $field_meta:tt [$($opt:tt)*] $($rest:tt)*) => (
+        ::core::compile_error!(::core::concat!(
+            "unknown `#[new]` field option: `", ::core::stringify!($($opt)*), "`"
+        ));
+    );
+
+    // The annotated struct itself is only emitted when coming from `NewInsertable!`
+    (@generate [entity] $opts:tt $meta:tt $struct_vis:tt $StructName:ident $generics:tt $id:tt $fields:tt $entity_fields:tt) => (
+        $crate::diesel_new! { @entity $opts $meta $struct_vis $StructName $generics $entity_fields }
+
+        // `HasNewInsertable` is only implemented for the struct emitted here, so
+        // this takes the place of the fields that `diesel_new!` passes on
+        $crate::diesel_new! { @generate [new] $opts $meta $struct_vis $StructName $generics $id $fields [has_new] }
+    );
+    // Searching for the New struct in rustdoc should turn up the struct too, so
+    // the struct gets the name of the New struct as an alias.
+    // `#[new(name = ...)]` is always the first option, or right after
+    // `#[new(cfg(...))]`
+    (@entity [[cfg $_cfg:tt] [name $New:ident] $($_opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @entity [$New] $($rest)* }
+    );
+    (@entity [[name $New:ident] $($_opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @entity [$New] $($rest)* }
+    );
+    (@entity [$New:ident] [$([$($struct_meta:tt)*])*] [$struct_vis:vis] $StructName:ident
+        [$([$($generics:tt)*] [$($param:tt)*])?]
+        [$([[$($field_meta:tt)*] [$field_vis:vis] $field_name:ident [$field_ty:ty]])*]
+    ) => (
+        $crate::paste! {
+            $(#[$($struct_meta)*])*
+            #[doc(alias = $New "")]
+            $struct_vis struct $StructName $(<$($generics)*>)? {
                 $(
-                    $(#[$field_meta])*
+                    $($field_meta)*
                     $field_vis $field_name: $field_ty,
                 )*
             }
         }
     );
-}
+    (@entity $_opts:tt $meta:tt $struct_vis:tt $StructName:ident $($rest:tt)*) => (
+        $crate::paste! {
+            $crate::diesel_new! { @entity [[< New $StructName >]] $meta $struct_vis $StructName $($rest)* }
+        }
+    );
+    // `#[new(cfg(...))]` leaves out everything generated from the struct, other
+    // than the struct itself, unless the predicate holds. It is always the first
+    // option, so that it doesn't have to be looked up, and `#[new(name = ...)]`
+    // always comes right after it
+    (@generate [new] [[cfg [$($cfg:tt)*]] [name $New:ident] $($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $generics:tt $id:tt $fields:tt $has_new:tt) => (
+        $(#[cfg $cfg])*
+        $crate::__diesel_new_value! { table_name [$($opts)*] [[< $StructName:snake s >]]
+            [$crate::diesel_new] { @generate_new [$($opts)*] $meta $struct_vis $StructName $New $generics $id $fields $has_new }
+        }
+    );
+    (@generate [new] [[cfg [$($cfg:tt)*]] $($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $generics:tt $id:tt $fields:tt $has_new:tt) => (
+        $(#[cfg $cfg])*
+        $crate::paste! {
+            $crate::__diesel_new_value! { table_name [$($opts)*] [[< $StructName:snake s >]]
+                [$crate::diesel_new] { @generate_new [$($opts)*] $meta $struct_vis $StructName [< New $StructName >] $generics $id $fields $has_new }
+            }
+        }
+    );
+    (@generate [new] [[name $New:ident] $($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $generics:tt $id:tt $fields:tt $has_new:tt) => (
+        $crate::__diesel_new_value! { table_name [$($opts)*] [[< $StructName:snake s >]]
+            [$crate::diesel_new] { @generate_new [$($opts)*] $meta $struct_vis $StructName $New $generics $id $fields $has_new }
+        }
+    );
+    (@generate [new] [$($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $generics:tt $id:tt $fields:tt $has_new:tt) => (
+        // Diesel infers the table name from the struct name when it isn't given,
+        // and the name of the New struct is pasted once for everything after
+        $crate::paste! {
+            $crate::__diesel_new_value! { table_name [$($opts)*] [[< $StructName:snake s >]]
+                [$crate::diesel_new] { @generate_new [$($opts)*] $meta $struct_vis $StructName [< New $StructName >] $generics $id $fields $has_new }
+            }
+        }
+    );
+    // `snapshot_new_struct!` only describes the fields of the generated struct
+    (@generate [snapshot] $opts:tt $meta:tt $struct_vis:tt $StructName:ident $_generics:tt $_id:tt
+        [$([$field_opts:tt $field_meta:tt [$field_vis:vis] $field_name:ident [$field_ty:ty]])*] $_entity_fields:tt
+    ) => (
+        &[$((::core::stringify!($field_name), ::core::stringify!($field_ty))),*]
+    );
 
-#[cfg(test)]
-mod tests {
-    use diesel::debug_query;
-    use diesel::prelude::*;
+    // Structs with generics only get the New structs and what can be generated
+    // for them without knowing how the generics are used
+    (@generate_new [$($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $New:ident [$generics:tt $params:tt] $id:tt $fields:tt $has_new:tt $table:tt) => (
+        $crate::diesel_new! { @generic_opts [$($opts)*] }
 
-    table! {
-        users(id) {
-            id -> Integer,
-            name -> Text,
+        $crate::diesel_new! { @allowed $StructName [$] [] [$($opts)*] }
+
+        $crate::__diesel_new_flag! { manual_insertable [$($opts)*] [] [#[derive(diesel::Insertable)]]
+            [$crate::diesel_new] { @new_structs [$($opts)*] $meta $struct_vis $StructName $New [$generics $params] $fields $table }
         }
-    }
 
-    super::diesel_new! {
-        #[derive(Debug, Clone, Queryable, AsChangeset)]
-        #[diesel(table_name = users)]
-        pub struct User {
-            id: i32,
-            pub name: String
+        $crate::diesel_new! { @skipped_columns $StructName $New [$generics $params] $id [$($opts)*] $table }
+
+        $crate::diesel_new! { @has_new $StructName $New [$generics $params] $has_new }
+
+        $crate::diesel_new! { @natural_key $StructName $New [$generics $params] $table [] $fields }
+
+        $crate::__diesel_new_flag! { check_nullable [$($opts)*] [check_nullable] []
+            [$crate::diesel_new] { @check_nullable $table $fields }
         }
-    }
 
-    use super::apply;
-    use super::NewInsertable;
+        $crate::__diesel_new_flag! { check_id [$($opts)*] [check_id] []
+            [$crate::diesel_new] { @check_id $id }
+        }
+    );
+    (@generate_new [$($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $New:ident [] $id:tt $fields:tt $has_new:tt $table:tt) => (
+        $crate::diesel_new! { @allowed $StructName [$] [] [$($opts)*] }
 
-    #[derive(Identifiable)]
-    #[apply(NewInsertable!)]
-    #[derive(Queryable, AsChangeset)]
-    #[diesel(table_name = users)]
-    pub struct SuperUser {
-        #[allow(dead_code)]
-        id: i32,
-        pub name: String,
-    }
+        $crate::__diesel_new_flag! { manual_insertable [$($opts)*] [] [#[derive(diesel::Insertable)]]
+            [$crate::diesel_new] { @new_structs [$($opts)*] $meta $struct_vis $StructName $New [] $fields $table }
+        }
 
-    #[test]
-    fn it_generates_a_new_struct() {
-        NewUser {
-            name: String::from("Ferris"),
-        };
-    }
+        $crate::__diesel_new_flag! { dynamic [$($opts)*] [dynamic] []
+            [$crate::diesel_new] { @dynamic $StructName $New $table }
+        }
 
-    #[test]
-    fn it_works_with_the_apply_attr_and_identifiable() {
-        NewSuperUser {
-            name: String::from("Ferris"),
-        };
+        $crate::diesel_new! { @skipped_columns $StructName $New [] $id [$($opts)*] $table }
+
+        $crate::diesel_new! { @has_new $StructName $New [] $has_new }
+
+        $crate::diesel_new! { @natural_key $StructName $New [] $table [] $fields }
+
+        $crate::__diesel_new_flag! { event [$($opts)*] [event] []
+            [$crate::diesel_new] { @event $struct_vis $StructName $New $id $fields }
+        }
+
+        $crate::__diesel_new_flag! { event [$($opts)*] [event] []
+            [$crate::diesel_new] { @event_create [$($opts)*] $StructName $New $id $table }
+        }
+
+        $crate::__diesel_new_flag! { update_from_new [$($opts)*] [update_from_new] []
+            [$crate::diesel_new] { @renamed update_from_new [$($opts)*] { @update_from_new $StructName $New $id $table } }
+        }
+
+        $crate::__diesel_new_flag! { conversions [$($opts)*] [conversions] []
+            [$crate::diesel_new] { @renamed from_new [$($opts)*] { @conversions [$($opts)*] [] $StructName $New $id $fields } }
+        }
+
+        $crate::__diesel_new_flag! { create [$($opts)*] [create] []
+            [$crate::diesel_new] { @renamed create [$($opts)*] { @create $StructName $New $table } }
+        }
+
+        $crate::__diesel_new_flag! { retry [$($opts)*] [retry] []
+            [$crate::diesel_new] { @renamed create_with_retry [$($opts)*] { @retry $StructName $New $table } }
+        }
+
+        $crate::__diesel_new_flag! { check_nullable [$($opts)*] [check_nullable] []
+            [$crate::diesel_new] { @check_nullable $table $fields }
+        }
+
+        $crate::__diesel_new_flag! { check_id [$($opts)*] [check_id] []
+            [$crate::diesel_new] { @check_id $id }
+        }
+
+        $crate::__diesel_new_value! { check_for_backend [$($opts)*] []
+            [$crate::diesel_new] { @check_for_backend $StructName $New $table }
+        }
+
+        $crate::__diesel_new_flag! { display [$($opts)*] [display] []
+            [$crate::diesel_new] { @display $StructName $New $table $table $fields }
+        }
+
+        $crate::__diesel_new_flag! { builder [$($opts)*] [builder] []
+            [$crate::diesel_new] { @renamed builder [$($opts)*] { @builder $struct_vis $StructName $New $fields } }
+        }
+
+        $crate::__diesel_new_flag! { from_json [$($opts)*] [from_json] []
+            [$crate::diesel_new] { @from_json $StructName $New $fields }
+        }
+
+        $crate::__diesel_new_flag! { raw_insert [$($opts)*] [raw_insert] []
+            [$crate::diesel_new] { @raw_insert $StructName $New $table $fields }
+        }
+
+        $crate::__diesel_new_flag! { from_form [$($opts)*] [from_form] []
+            [$crate::diesel_new] { @from_form $StructName $New $fields }
+        }
+
+        $crate::__diesel_new_flag! { verify_schema [$($opts)*] [verify_schema] []
+            [$crate::diesel_new] { @verify_schema $StructName $New $table $fields }
+        }
+
+        $crate::__diesel_new_flag! { reordered [$($opts)*] [$($opts)*] []
+            [$crate::diesel_new] { @reordered }
+        }
+
+        $crate::__diesel_new_flag! { with_id [$($opts)*] [with_id] []
+            [$crate::diesel_new] { @renamed import [$($opts)*] { @with_id $meta $struct_vis $StructName [$id $fields] $table } }
+        }
+
+        $crate::__diesel_new_value! { graphql [$($opts)*] []
+            [$crate::diesel_new] { @graphql $struct_vis $StructName $New $table }
+        }
+
+        $crate::__diesel_new_flag! { wasm [$($opts)*] [wasm] []
+            [$crate::diesel_new] { @wasm }
+        }
+
+        $crate::__diesel_new_flag! { proptest [$($opts)*] [proptest] []
+            [$crate::diesel_new] { @proptest $StructName $New $table $fields }
+        }
+
+        $crate::__diesel_new_flag! { update_struct [$($opts)*] [update_struct] []
+            [$crate::diesel_new] { @update_struct [$($opts)*] $struct_vis $StructName $id $table $fields }
+        }
+
+        $crate::__diesel_new_flag! { diff [$($opts)*] [diff] []
+            [$crate::diesel_new] { @diff_requires [$($opts)*] }
+        }
+
+        $crate::__diesel_new_flag! { cqrs [$($opts)*] [cqrs] []
+            [$crate::diesel_new] { @cqrs_meta $struct_vis $StructName $table [$id $fields] [] $meta }
+        }
+    );
+
+    // Everything else generated from options has to know how the generics are
+    // used, so those options fail to compile rather than being left out
+    (@generic_opts [$([$opt:ident $($_value:tt)*])*]) => (
+        $($crate::__diesel_new_generic_opt! { $opt })*
+    );
+
+    // The structs of `with_id` and `cqrs` are the id followed by the fields of
+    // the New struct, so they would be read into the wrong fields by
+    // `Queryable` if the primary key is anywhere else in the struct, or if any
+    // of its fields are skipped
+    (@reordered []) => ();
+    (@reordered [$($opts:tt)+]) => (
+        $crate::__diesel_new_flag! { with_id [$($opts)+] [with_id] []
+            [$crate::diesel_new] { @reordered_with }
+        }
+
+        $crate::__diesel_new_flag! { cqrs [$($opts)+] [cqrs] []
+            [$crate::diesel_new] { @reordered_with }
+        }
+    );
+    (@reordered_with []) => ();
+    (@reordered_with [$option:ident]) => (
+        ::core::compile_error!(::core::concat!(
+            "`#[new(", ::core::stringify!($option), ")]` needs every field of the struct in the New struct, ",
+            "other than a `#[new(primary_key)]` that is the first field"
+        ));
+    );
+
+    // Lints can't be allowed on a macro invocation, so everything generated for
+    // a struct is passed through a throwaway macro, which allows the lints of
+    // every `#[new(allow(...))]` on each item. The `$` is passed in as a token
+    // to write the repetitions of that macro
+    (@allowed $StructName:ident $d:tt [$($lints:tt)*] [[allow $lint:tt] $($opts:tt)*]) => (
+        $crate::diesel_new! { @allowed $StructName $d [$($lints)* $lint] [$($opts)*] }
+    );
+    (@allowed $StructName:ident $d:tt $lints:tt [$_opt:tt $($opts:tt)*]) => (
+        $crate::diesel_new! { @allowed $StructName $d $lints [$($opts)*] }
+    );
+    (@allowed $StructName:ident [$d:tt] [$($lint:tt)*] []) => (
+        $crate::paste! {
+            #[allow(unused_macros)]
+            macro_rules! [< __diesel_new_ $StructName _allowed >] {
+                ($d($d item:item)*) => ($d($(#[allow $lint])* $d item)*);
+            }
+        }
+    );
+
+    // Generates the method of an option that is set, under its name from
+    // `#[new(rename(...))]` if it has one
+    (@renamed $method:ident $opts:tt $args:tt []) => ();
+    (@renamed $method:ident $opts:tt { $($args:tt)* } [$_flag:ident]) => (
+        $crate::__diesel_new_value! { rename $method $opts [$method] [$crate::diesel_new] { $($args)* } }
+    );
+
+    (@new_structs $opts:tt $meta:tt $struct_vis:tt $StructName:ident $New:ident $generics:tt $fields:tt $table:tt $derive_insertable:tt) => (
+        $crate::diesel_new! { @docs $StructName $opts [] [] []
+            [$opts $meta $struct_vis $StructName $New $generics $fields $table $derive_insertable]
+        }
+    );
+
+    // `#[new(doc = "...")]` replaces the docs copied from the struct, which tend
+    // to describe the row rather than the insert, and `#[new(append_doc = "...")]`
+    // adds to them. `{Entity}` is replaced with the name of the struct, and the
+    // parts of each line are concatenated by `paste!`
+    (@docs $StructName:ident [[doc $line:tt] $($opts:tt)*] $docs:tt $appended:tt $derives:tt $state:tt) => (
+        $crate::diesel_new! { @doc_line $StructName [] $line [doc [$($opts)*] $docs $appended $derives $state] }
+    );
+    (@docs $StructName:ident [[append_doc $line:tt] $($opts:tt)*] $docs:tt $appended:tt $derives:tt $state:tt) => (
+        $crate::diesel_new! { @doc_line $StructName [] $line [append_doc [$($opts)*] $docs $appended $derives $state] }
+    );
+    // `#[new(wasm)]` only goes on the New structs, as the struct itself is
+    // rarely something a frontend can use. It has to come before the derives
+    (@docs $StructName:ident [[wasm] $($opts:tt)*] $docs:tt $appended:tt $derives:tt [$struct_opts:tt [$($meta:tt)*] $($state:tt)*]) => (
+        $crate::diesel_new! { @docs $StructName [$($opts)*] $docs $appended $derives
+            [$struct_opts [[$crate::wasm_bindgen::prelude::wasm_bindgen(getter_with_clone, wasm_bindgen = $crate::wasm_bindgen)] $($meta)*] $($state)*]
+        }
+    );
+    // `#[new(attr(...))]` is added to the New structs only, e.g. derives for
+    // sending them through a queue that the struct itself doesn't need
+    (@docs $StructName:ident [[attr $attr:tt] $($opts:tt)*] $docs:tt [$($appended:tt)*] $derives:tt $state:tt) => (
+        $crate::diesel_new! { @docs $StructName [$($opts)*] $docs [$($appended)* $attr] $derives $state }
+    );
+    // `#[new(derive(...))]` replaces the derives copied from the struct, for
+    // derives that only make sense for rows
+    (@docs $StructName:ident [[derive $derive:tt] $($opts:tt)*] $docs:tt $appended:tt [$($derives:tt)*] $state:tt) => (
+        $crate::diesel_new! { @docs $StructName [$($opts)*] $docs $appended [$($derives)* $derive] $state }
+    );
+    // With `#[new(default)]` fields, `Insertable` is implemented by hand for
+    // each New struct instead of being derived
+    (@docs $StructName:ident [[default $($default:tt)*] $($opts:tt)*] $docs:tt $appended:tt $derives:tt
+        [$($state:tt)*]
+    ) => (
+        $crate::diesel_new! { @docs_default $StructName [$($opts)*] $docs $appended $derives [] [[$($default)*]] [$($state)*] }
+    );
+    (@docs $StructName:ident [$_opt:tt $($opts:tt)*] $docs:tt $appended:tt $derives:tt $state:tt) => (
+        $crate::diesel_new! { @docs $StructName [$($opts)*] $docs $appended $derives $state }
+    );
+    // The name of the struct is pasted into a `#[doc(alias)]` so that searching
+    // for the struct in rustdoc turns up the structs generated for it
+    (@docs $StructName:ident [] $docs:tt [$($appended:tt)*] [$([$($derive:tt)*])*] [$opts:tt $meta:tt $($state:tt)*]) => (
+        $crate::diesel_new! { @new_meta [] $meta $docs [$([$($derive)*])*]
+            [[$($appended)* [doc(alias = $StructName "")]] $opts $($state)*]
+        }
+    );
+
+    (@docs_default $StructName:ident $opts:tt $docs:tt $appended:tt $derives:tt [$($state:tt)*] [$($defaults:tt)*]
+        [$state_opts:tt $meta:tt $struct_vis:tt $S:ident $New:ident $generics:tt $fields:tt $table:tt [@defaults [$($more:tt)*]]]
+    ) => (
+        $crate::diesel_new! { @docs $StructName $opts $docs $appended $derives
+            [$state_opts $meta $struct_vis $S $New $generics $fields $table [@defaults [$($more)* $($defaults)*]]]
+        }
+    );
+    (@docs_default $StructName:ident $opts:tt $docs:tt $appended:tt $derives:tt [] $defaults:tt
+        [$state_opts:tt $meta:tt $struct_vis:tt $S:ident $New:ident $generics:tt $fields:tt $table:tt [$(#[$($_insertable:tt)*])+]]
+    ) => (
+        $crate::diesel_new! { @docs $StructName $opts $docs $appended $derives
+            [$state_opts $meta $struct_vis $S $New $generics $fields $table [@defaults $defaults]]
+        }
+    );
+    (@docs_default $StructName:ident $opts:tt $docs:tt $appended:tt $derives:tt [] $defaults:tt [$($state:tt)*]) => (
+        $crate::diesel_new! { @docs $StructName $opts $docs $appended $derives [$($state)*] }
+    );
+
+    (@doc_line $StructName:ident [$($done:tt)*] [{Entity} $($parts:tt)*] $k:tt) => (
+        $crate::diesel_new! { @doc_line $StructName [$($done)* $StructName] [$($parts)*] $k }
+    );
+    (@doc_line $StructName:ident [$($done:tt)*] [$part:literal $($parts:tt)*] $k:tt) => (
+        $crate::diesel_new! { @doc_line $StructName [$($done)* $part] [$($parts)*] $k }
+    );
+    (@doc_line $StructName:ident [$($done:tt)*] [] [doc $opts:tt [$($docs:tt)*] $appended:tt $derives:tt $state:tt]) => (
+        $crate::diesel_new! { @docs $StructName $opts [$($docs)* [doc = $($done)*]] $appended $derives $state }
+    );
+    (@doc_line $StructName:ident [$($done:tt)*] [] [append_doc $opts:tt $docs:tt [$($appended:tt)*] $derives:tt $state:tt]) => (
+        $crate::diesel_new! { @docs $StructName $opts $docs [$($appended)* [doc = $($done)*]] $derives $state }
+    );
+    (@doc_line $StructName:ident $done:tt [$($parts:tt)*] $k:tt) => (
+        ::core::compile_error!(::core::concat!(
+            "expected string literals and `{Entity}` in `#[new(doc = ...)]`, found: `",
+            ::core::stringify!($($parts)*), "`"
+        ));
+    );
+
+    // The attributes of the struct are copied onto the New structs, other than
+    // the docs and derives that are replaced, and `Identifiable`, as the New
+    // structs have no id. Replaced derives go where the first derive was, along
+    // with `Insertable`, as `#[diesel(...)]` can't come before a derive that
+    // introduces it
+    (@new_meta $kept:tt [[doc = $($_doc:tt)*] $($meta:tt)*] [$($docs:tt)+] $derives:tt $state:tt) => (
+        $crate::diesel_new! { @new_meta $kept [$($meta)*] [$($docs)+] $derives $state }
+    );
+    (@new_meta $kept:tt [[derive $_paths:tt] $($meta:tt)*] $docs:tt [replaced] $state:tt) => (
+        $crate::diesel_new! { @new_meta $kept [$($meta)*] $docs [replaced] $state }
+    );
+    (@new_meta [$($kept:tt)*] [[derive $_paths:tt] $($meta:tt)*] $docs:tt [$([$($derive:tt)*])+]
+        [$added:tt $opts:tt $struct_vis:tt $StructName:ident $New:ident $generics:tt $fields:tt $table:tt [@defaults $defaults:tt]]
+    ) => (
+        $crate::diesel_new! { @new_meta [$($kept)* $([derive($($derive)*)])+] [$($meta)*] $docs [replaced]
+            [$added $opts $struct_vis $StructName $New $generics $fields $table [@defaults $defaults]]
+        }
+    );
+    (@new_meta [$($kept:tt)*] [[derive $_paths:tt] $($meta:tt)*] $docs:tt [$([$($derive:tt)*])+]
+        [$added:tt $opts:tt $struct_vis:tt $StructName:ident $New:ident $generics:tt $fields:tt $table:tt [$(#[$($insertable:tt)*])?]]
+    ) => (
+        $crate::diesel_new! { @new_meta [$($kept)* $([derive($($derive)*)])+ $([$($insertable)*])?] [$($meta)*] $docs [replaced]
+            [$added $opts $struct_vis $StructName $New $generics $fields $table []]
+        }
+    );
+    (@new_meta $kept:tt [[derive($($paths:tt)*)] $($meta:tt)*] $docs:tt [] $state:tt) => (
+        $crate::diesel_new! { @identifiable [] [$($paths)*] [new_meta $kept [$($meta)*] $docs $state] }
+    );
+    (@new_meta [$($kept:tt)*] [$attr:tt $($meta:tt)*] $docs:tt $derives:tt $state:tt) => (
+        $crate::diesel_new! { @new_meta [$($kept)* $attr] [$($meta)*] $docs $derives $state }
+    );
+    (@new_meta [$($kept:tt)*] [] [$($docs:tt)*] [replaced] [[$($added:tt)*] $opts:tt $($state:tt)*]) => (
+        $crate::diesel_new! { @documented_structs $opts [$($kept)* $($docs)* $($added)*] $($state)* }
+    );
+    (@new_meta [$($kept:tt)*] [] [$($docs:tt)*] [$([$($derive:tt)*])*] [[$($added:tt)*] $opts:tt $($state:tt)*]) => (
+        $crate::diesel_new! { @documented_structs $opts [$($kept)* $([derive($($derive)*)])* $($docs)* $($added)*] $($state)* }
+    );
+
+    (@identifiable [$($paths:tt)*] [] [new_meta [$($kept:tt)*] $meta:tt $docs:tt $state:tt]) => (
+        $crate::diesel_new! { @new_meta [$($kept)* [derive($($paths)*)]] $meta $docs [] $state }
+    );
+    (@identifiable [$($paths:tt)*] [] [with_id [$($kept:tt)*] $meta:tt $state:tt]) => (
+        $crate::diesel_new! { @with_id [$($kept)* [derive($($paths)*)]] $meta $state }
+    );
+    (@identifiable $paths:tt [$(::)? $(diesel::)? Identifiable $(, $($rest:tt)*)?] $k:tt) => (
+        $crate::diesel_new! { @identifiable $paths [$($($rest)*)?] $k }
+    );
+    (@identifiable [$($paths:tt)*] [:: $($segment:ident)::+ $(, $($rest:tt)*)?] $k:tt) => (
+        $crate::diesel_new! { @identifiable [$($paths)* :: $($segment)::+,] [$($($rest)*)?] $k }
+    );
+    (@identifiable [$($paths:tt)*] [$($segment:ident)::+ $(, $($rest:tt)*)?] $k:tt) => (
+        $crate::diesel_new! { @identifiable [$($paths)* $($segment)::+,] [$($($rest)*)?] $k }
+    );
+
+    (@documented_structs $opts:tt $meta:tt $struct_vis:tt $StructName:ident $New:ident $generics:tt $fields:tt $table:tt $derive_insertable:tt) => (
+        $crate::diesel_new! { @struct $StructName $meta $struct_vis [$New] $generics $fields $table $derive_insertable }
+        $crate::diesel_new! { @groups $opts $meta $struct_vis $StructName $New $generics $fields $table $derive_insertable }
+    );
+
+    // Every `#[new(group = "...")]` on the struct gets its own New struct with
+    // only the fields marked with the same group. Literals can't be compared
+    // by a declarative macro, so a throwaway macro is generated to do it
+    (@groups [] $($_rest:tt)*) => ();
+    (@groups [[group $group:literal] $($opts:tt)*] $meta:tt $struct_vis:tt $StructName:ident $New:ident $generics:tt $fields:tt $table:tt $derive_insertable:tt) => (
+        $crate::paste! {
+            #[allow(unused_macros)]
+            macro_rules! [< __diesel_new_ $StructName _in_ $group >] {
+                ([group $group] $yes:tt $no:tt) => ($crate::diesel_new! $yes);
+                ($other:tt $yes:tt $no:tt) => ($crate::diesel_new! $no);
+            }
+
+            $crate::diesel_new! { @group_fields [< __diesel_new_ $StructName _in_ $group >] [] $fields
+                [$StructName $meta $struct_vis [$New $group:camel] $generics $table $derive_insertable]
+            }
+        }
+
+        $crate::diesel_new! { @groups [$($opts)*] $meta $struct_vis $StructName $New $generics $fields $table $derive_insertable }
+    );
+    (@groups [$_opt:tt $($opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @groups [$($opts)*] $($rest)* }
+    );
+
+    (@group_fields $in_group:ident [$($kept:tt)*] [] [$StructName:ident $meta:tt $struct_vis:tt $new_name:tt $generics:tt $table:tt $derive_insertable:tt]) => (
+        $crate::diesel_new! { @struct $StructName $meta $struct_vis $new_name $generics [$($kept)*] $table $derive_insertable }
+    );
+    // Fields without any options can't be in a group, which saves some steps
+    (@group_fields $in_group:ident $kept:tt [[[$([nullable])?] $($_field:tt)*] $($fields:tt)*] $struct:tt) => (
+        $crate::diesel_new! { @group_fields $in_group $kept [$($fields)*] $struct }
+    );
+    (@group_fields $in_group:ident $kept:tt [[$field_opts:tt $($field:tt)*] $($fields:tt)*] $struct:tt) => (
+        $crate::diesel_new! { @group_field $in_group $kept $field_opts [$field_opts $($field)*] [$($fields)*] $struct }
+    );
+    (@group_field $in_group:ident $kept:tt [] $field:tt $fields:tt $struct:tt) => (
+        $crate::diesel_new! { @group_fields $in_group $kept $fields $struct }
+    );
+    (@group_field $in_group:ident [$($kept:tt)*] [$opt:tt $($opts:tt)*] $field:tt $fields:tt $struct:tt) => (
+        $in_group! { $opt
+            { @group_fields $in_group [$($kept)* $field] $fields $struct }
+            { @group_field $in_group [$($kept)*] [$($opts)*] $field $fields $struct }
+        }
+    );
+
+    // `#[new(check_nullable)]` fails to compile when a field is an `Option` but
+    // its column isn't `Nullable`, or the other way around
+    (@check_nullable $table:tt $fields:tt []) => ();
+    (@check_nullable $table:tt [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident $field_ty:tt])*] [check_nullable]) => (
+        const _: () = {
+            #[allow(dead_code)]
+            fn nullable<Column>()
+            where
+                Column: diesel::Expression,
+                Column::SqlType: diesel::sql_types::SqlType<IsNull = diesel::sql_types::is_nullable::IsNullable>,
+            {
+            }
+
+            #[allow(dead_code)]
+            fn not_null<Column>()
+            where
+                Column: diesel::Expression,
+                Column::SqlType: diesel::sql_types::SqlType<IsNull = diesel::sql_types::is_nullable::NotNull>,
+            {
+            }
+
+            #[allow(dead_code)]
+            fn check_nullable() {
+                $($crate::__diesel_new_nullability! { $table $field_name [not_null] $field_opts })*
+            }
+        };
+    );
+
+    // `#[new(update_struct)]` generates a changeset with every field wrapped in
+    // an `Option`, where `None` leaves the column as it is. Only docs and the
+    // column name are copied onto its fields.
+    //
+    // With `#[new(treat_none_as_null)]` on the struct or on a field, `Option`
+    // fields are kept as they are instead, so that `None` sets the column to
+    // `NULL` and the column is always written
+    (@update_struct $opts:tt $struct_vis:tt $StructName:ident $id:tt $table:tt $fields:tt []) => ();
+    (@update_struct $opts:tt $struct_vis:tt $StructName:ident $id:tt $table:tt $fields:tt [update_struct]) => (
+        $crate::__diesel_new_flag! { treat_none_as_null $opts [null] []
+            [$crate::diesel_new] { @update_fields [$opts $struct_vis $StructName $id $table] [] $fields }
+        }
+    );
+    // Fields without options or attributes other than docs are the common case
+    (@update_fields $k:tt [$($done:tt)*]
+        [[[$([nullable])?] [$(#[doc $($doc:tt)*])*] $field_vis:tt $field_name:ident [$field_ty:ty]] $($fields:tt)*] []
+    ) => (
+        $crate::diesel_new! { @update_fields $k
+            [$($done)* [[$(#[doc $($doc)*])*] $field_vis $field_name [::core::option::Option<$field_ty>] [::core::option::Option::Some] some]]
+            [$($fields)*] []
+        }
+    );
+    (@update_fields $k:tt [$($done:tt)*]
+        [[[] [$(#[doc $($doc:tt)*])*] $field_vis:tt $field_name:ident [$field_ty:ty]] $($fields:tt)*] [null]
+    ) => (
+        $crate::diesel_new! { @update_fields $k
+            [$($done)* [[$(#[doc $($doc)*])*] $field_vis $field_name [::core::option::Option<$field_ty>] [::core::option::Option::Some] some]]
+            [$($fields)*] [null]
+        }
+    );
+    (@update_fields $k:tt $done:tt [[$field_opts:tt $field_meta:tt $($field:tt)*] $($fields:tt)*] $null:tt) => (
+        $crate::diesel_new! { @update_field_opts [] [] [] $field_opts [[] $field_meta [$($field)*] $k $done [$($fields)*] $null] }
+    );
+    (@update_field_opts $_nullable:tt $column:tt $none_as_null:tt [[nullable] $($opts:tt)*] $f:tt) => (
+        $crate::diesel_new! { @update_field_opts [nullable] $column $none_as_null [$($opts)*] $f }
+    );
+    (@update_field_opts $nullable:tt $_column:tt $none_as_null:tt [[column_name $column:ident] $($opts:tt)*] $f:tt) => (
+        $crate::diesel_new! { @update_field_opts $nullable [#[diesel(column_name = $column)]] $none_as_null [$($opts)*] $f }
+    );
+    // `#[new(immutable)]` fields aren't in the changeset at all
+    (@update_field_opts $_nullable:tt $_column:tt $_none_as_null:tt [[immutable] $($_opts:tt)*]
+        [$_kept:tt $_field_meta:tt $_field:tt $k:tt $done:tt $fields:tt $null:tt]
+    ) => (
+        $crate::diesel_new! { @update_fields $k $done $fields $null }
+    );
+    // Fields with `#[new(type = ...)]` or a type for the backend keep the type
+    // of the struct, which is what is written to the column
+    (@update_field_opts $nullable:tt $column:tt $none_as_null:tt [[entity_ty $entity_ty:ty] $($opts:tt)*]
+        [$kept:tt $field_meta:tt [$field_vis:tt $field_name:ident $_field_ty:tt] $($f:tt)*]
+    ) => (
+        $crate::diesel_new! { @update_field_opts $nullable $column $none_as_null [$($opts)*]
+            [$kept $field_meta [$field_vis $field_name [$entity_ty]] $($f)*]
+        }
+    );
+    (@update_field_opts $nullable:tt $column:tt $_none_as_null:tt [[treat_none_as_null] $($opts:tt)*] $f:tt) => (
+        $crate::diesel_new! { @update_field_opts $nullable $column [null] [$($opts)*] $f }
+    );
+    (@update_field_opts $nullable:tt $column:tt $none_as_null:tt [$_opt:tt $($opts:tt)*] $f:tt) => (
+        $crate::diesel_new! { @update_field_opts $nullable $column $none_as_null [$($opts)*] $f }
+    );
+    // Anything other than docs is dropped from the attributes of the field
+    (@update_field_opts $nullable:tt $column:tt $none_as_null:tt [] [[$($kept:tt)*] [#[doc $($doc:tt)*] $($field_meta:tt)*] $($f:tt)*]) => (
+        $crate::diesel_new! { @update_field_opts $nullable $column $none_as_null [] [[$($kept)* #[doc $($doc)*]] [$($field_meta)*] $($f)*] }
+    );
+    (@update_field_opts $nullable:tt $column:tt $none_as_null:tt [] [$kept:tt [#$_attr:tt $($field_meta:tt)*] $($f:tt)*]) => (
+        $crate::diesel_new! { @update_field_opts $nullable $column $none_as_null [] [$kept [$($field_meta)*] $($f)*] }
+    );
+    (@update_field_opts [] $column:tt [null] [] [$kept:tt [] [$field_vis:tt $field_name:ident $field_ty:tt] $($_f:tt)*]) => (
+        ::core::compile_error!(::core::concat!(
+            "`#[new(treat_none_as_null)]` needs `", ::core::stringify!($field_name), "` to be an `Option`"
+        ));
+    );
+    (@update_field_opts [nullable] [$($column:tt)*] [null] []
+        [[$($kept:tt)*] [] [$field_vis:tt $field_name:ident [$field_ty:ty]] $k:tt [$($done:tt)*] $fields:tt $null:tt]
+    ) => (
+        $crate::diesel_new! { @update_fields $k
+            [$($done)* [[$($kept)* $($column)* #[diesel(treat_none_as_null = true)]] $field_vis $field_name [$field_ty] [::core::convert::identity] null]]
+            $fields $null
+        }
+    );
+    (@update_field_opts [nullable] $column:tt [] [] [$kept:tt [] $field:tt $k:tt $done:tt $fields:tt [null]]) => (
+        $crate::diesel_new! { @update_field_opts [nullable] $column [null] [] [$kept [] $field $k $done $fields [null]] }
+    );
+    (@update_field_opts $_nullable:tt [$($column:tt)*] [] []
+        [[$($kept:tt)*] [] [$field_vis:tt $field_name:ident [$field_ty:ty]] $k:tt [$($done:tt)*] $fields:tt $null:tt]
+    ) => (
+        $crate::diesel_new! { @update_fields $k
+            [$($done)* [[$($kept)* $($column)*] $field_vis $field_name [::core::option::Option<$field_ty>] [::core::option::Option::Some] some]]
+            $fields $null
+        }
+    );
+    (@update_fields [$opts:tt [$struct_vis:vis] $StructName:ident $id:tt [$($table:tt)*]]
+        [$([[$($field_meta:tt)*] [$field_vis:vis] $field_name:ident [$field_ty:ty] [$($wrap:tt)*] $kind:ident])*] [] $_null:tt
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[doc = ::core::concat!(
+                    "Changeset for updating any of the columns of a [`", ::core::stringify!($StructName),
+                    "`], where `None` leaves a column as it is",
+                )]
+                #[derive(Default, diesel::AsChangeset)]
+                #[diesel(table_name = $($table)*)]
+                $struct_vis struct [< Update $StructName >] {
+                    $(
+                        $($field_meta)*
+                        $field_vis $field_name: $field_ty,
+                    )*
+                }
+
+                // Deprecated fields are only moved, which shouldn't warn
+                #[allow(deprecated)]
+                impl ::core::convert::From<$StructName> for [< Update $StructName >] {
+                    #[allow(unused_variables)]
+                    fn from(row: $StructName) -> Self {
+                        Self {
+                            $($field_name: $($wrap)*(row.$field_name),)*
+                        }
+                    }
+                }
+
+                // Deprecated fields are only checked, which shouldn't warn
+                #[allow(dead_code, deprecated)]
+                impl [< Update $StructName >] {
+                    /// Whether no column would be written, which Diesel reports as an error
+                    /// rather than as an `UPDATE` that does nothing. Fields that are written
+                    /// even when they are `None` mean that it is never empty
+                    pub fn is_empty(&self) -> bool {
+                        true $(&& $crate::diesel_new!(@update_unset $kind self.$field_name))*
+                    }
+                }
+            }
+        }
+
+        $crate::diesel_new! { @update_row $StructName $id [$($table)*] }
+
+        $crate::__diesel_new_flag! { diff $opts [diff] []
+            [$crate::diesel_new] { @renamed diff $opts { @diff $StructName [$([$field_name $kind])*] } }
+        }
+    );
+
+    (@update_unset some $($field:tt)*) => ($($field)*.is_none());
+    (@update_unset null $($_field:tt)*) => (false);
+
+    (@update_row $StructName:ident [no_id] $table:tt) => ();
+    (@update_row $StructName:ident [$id_opts:tt $id_meta:tt $id_field_vis:tt $_id_name:ident [$id_type:ty]] [$($table:tt)*]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl [< Update $StructName >] {
+                    #[doc = ::core::concat!(
+                        "Updates the row with the given id and returns it as a [`",
+                        ::core::stringify!($StructName),
+                        "`], or only reads it when the changeset [`is_empty`](Self::is_empty), ",
+                        "such as for a `PATCH` without any fields",
+                    )]
+                    pub fn update<'query, Conn>(self, id: $id_type, conn: &mut Conn) -> diesel::QueryResult<$StructName>
+                    where
+                        diesel::dsl::Find<$($table)*::table, $id_type>:
+                            diesel::query_dsl::LoadQuery<'query, Conn, $StructName>,
+                        diesel::dsl::Update<diesel::dsl::Find<$($table)*::table, $id_type>, Self>:
+                            diesel::query_dsl::LoadQuery<'query, Conn, $StructName>,
+                    {
+                        if self.is_empty() {
+                            diesel::RunQueryDsl::get_result(diesel::QueryDsl::find($($table)*::table, id), conn)
+                        } else {
+                            diesel::RunQueryDsl::get_result(
+                                diesel::update(diesel::QueryDsl::find($($table)*::table, id)).set(self),
+                                conn,
+                            )
+                        }
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(diff)]` compares two snapshots of a row, so it needs the changeset
+    // from `#[new(update_struct)]` to put the differences in
+    (@diff $StructName:ident [$([$field_name:ident $kind:ident])*] [$method:ident]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                // Deprecated fields are only compared and cloned, which shouldn't warn
+                #[allow(deprecated)]
+                impl [< Update $StructName >] {
+                    #[doc = ::core::concat!(
+                        "Sets only the fields of `after` that differ from `before`, for the ",
+                        "smallest `UPDATE` (or audit record) that takes one [`",
+                        ::core::stringify!($StructName), "`] to the other",
+                    )]
+                    #[allow(unused_variables)]
+                    pub fn $method(before: &$StructName, after: &$StructName) -> Self {
+                        Self {
+                            $($field_name: $crate::diesel_new!(@diff_field $kind before after $field_name),)*
+                        }
+                    }
+                }
+            }
+        }
+    );
+    (@diff_field some $before:ident $after:ident $field_name:ident) => (
+        if $before.$field_name == $after.$field_name {
+            ::core::option::Option::None
+        } else {
+            ::core::option::Option::Some(::core::clone::Clone::clone(&$after.$field_name))
+        }
+    );
+    // `None` would set the column to `NULL` here, so it is always written
+    (@diff_field null $before:ident $after:ident $field_name:ident) => (
+        ::core::clone::Clone::clone(&$after.$field_name)
+    );
+    (@diff_requires $opts:tt []) => ();
+    (@diff_requires $opts:tt [diff]) => (
+        $crate::__diesel_new_flag! { update_struct $opts [update_struct] []
+            [$crate::diesel_new] { @diff_requires }
+        }
+    );
+    (@diff_requires []) => (
+        ::core::compile_error!("`#[new(diff)]` requires `#[new(update_struct)]`");
+    );
+    (@diff_requires [update_struct]) => ();
+
+    // `#[new(cqrs)]` generates a read model and a write model that only derive
+    // what their side needs, so none of the attributes of the struct are copied
+    // onto them other than docs and `#[diesel(...)]`
+    (@cqrs_meta $struct_vis:tt $StructName:ident $table:tt $fields:tt $kept:tt $meta:tt []) => ();
+    (@cqrs_meta $struct_vis:tt $StructName:ident $table:tt $fields:tt [$($kept:tt)*] [[doc $($doc:tt)*] $($meta:tt)*] [cqrs]) => (
+        $crate::diesel_new! { @cqrs_meta $struct_vis $StructName $table $fields [$($kept)* [doc $($doc)*]] [$($meta)*] [cqrs] }
+    );
+    (@cqrs_meta $struct_vis:tt $StructName:ident $table:tt $fields:tt $kept:tt [$_attr:tt $($meta:tt)*] [cqrs]) => (
+        $crate::diesel_new! { @cqrs_meta $struct_vis $StructName $table $fields $kept [$($meta)*] [cqrs] }
+    );
+    (@cqrs_meta $struct_vis:tt $StructName:ident $table:tt [[no_id] $fields:tt] $kept:tt [] [cqrs]) => (
+        $crate::diesel_new! { @cqrs $kept $struct_vis $StructName $table [] $fields [cqrs] }
+    );
+    (@cqrs_meta $struct_vis:tt $StructName:ident $table:tt [$id:tt [$($fields:tt)*]] $kept:tt [] [cqrs]) => (
+        $crate::diesel_new! { @cqrs $kept $struct_vis $StructName $table [] [$id $($fields)*] [cqrs] }
+    );
+    (@cqrs $meta:tt $struct_vis:tt $StructName:ident $table:tt [$($done:tt)*]
+        [[$_opts:tt [$(#[doc $($doc:tt)*])*] $($field:tt)*] $($fields:tt)*] $cqrs:tt
+    ) => (
+        $crate::diesel_new! { @cqrs $meta $struct_vis $StructName $table
+            [$($done)* [[$(#[doc $($doc)*])*] $($field)*]] [$($fields)*] $cqrs
+        }
+    );
+    (@cqrs $meta:tt $struct_vis:tt $StructName:ident $table:tt $done:tt
+        [[$_opts:tt [$($field_meta:tt)*] $($field:tt)*] $($fields:tt)*] $cqrs:tt
+    ) => (
+        $crate::diesel_new! { @cqrs_field_meta [] [$($field_meta)*]
+            [$meta $struct_vis $StructName $table $done [$($field)*] [$($fields)*] $cqrs]
+        }
+    );
+    (@cqrs_field_meta [$($kept:tt)*] [#[doc $($doc:tt)*] $($field_meta:tt)*] $k:tt) => (
+        $crate::diesel_new! { @cqrs_field_meta [$($kept)* #[doc $($doc)*]] [$($field_meta)*] $k }
+    );
+    (@cqrs_field_meta [$($kept:tt)*] [#[diesel $($diesel:tt)*] $($field_meta:tt)*] $k:tt) => (
+        $crate::diesel_new! { @cqrs_field_meta [$($kept)* #[diesel $($diesel)*]] [$($field_meta)*] $k }
+    );
+    (@cqrs_field_meta $kept:tt [#$_attr:tt $($field_meta:tt)*] $k:tt) => (
+        $crate::diesel_new! { @cqrs_field_meta $kept [$($field_meta)*] $k }
+    );
+    (@cqrs_field_meta $kept:tt []
+        [$meta:tt $struct_vis:tt $StructName:ident $table:tt [$($done:tt)*] [$($field:tt)*] $fields:tt $cqrs:tt]
+    ) => (
+        $crate::diesel_new! { @cqrs $meta $struct_vis $StructName $table [$($done)* [$kept $($field)*]] $fields $cqrs }
+    );
+    (@cqrs [$([$($struct_meta:tt)*])*] [$struct_vis:vis] $StructName:ident [$($table:tt)*]
+        [
+            [[$($id_meta:tt)*] [$id_vis:vis] $id_name:ident [$id_type:ty]]
+            $([[$($field_meta:tt)*] [$field_vis:vis] $field_name:ident [$field_ty:ty]])*
+        ]
+        [] [cqrs]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                $(#[$($struct_meta)*])*
+                #[doc = ""]
+                #[doc = ::core::concat!("The read model of [`", ::core::stringify!($StructName), "`]")]
+                #[derive(diesel::Queryable, diesel::Selectable)]
+                #[diesel(table_name = $($table)*)]
+                $struct_vis struct [< $StructName ReadModel >] {
+                    $($id_meta)*
+                    $id_vis $id_name: $id_type,
+                    $(
+                        $($field_meta)*
+                        $field_vis $field_name: $field_ty,
+                    )*
+                }
+            }
+        }
+
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                $(#[$($struct_meta)*])*
+                #[doc = ""]
+                #[doc = ::core::concat!("The write model of [`", ::core::stringify!($StructName), "`]")]
+                #[derive(diesel::Insertable)]
+                #[diesel(table_name = $($table)*)]
+                $struct_vis struct [< $StructName WriteModel >] {
+                    $(
+                        $($field_meta)*
+                        $field_vis $field_name: $field_ty,
+                    )*
+                }
+            }
+        }
+    );
+
+    // `#[new(raw_insert)]` writes the insert as SQL, with the columns in the
+    // order of the fields, and binds the fields in the same order
+    (@raw_insert $StructName:ident $New:ident $table:tt $fields:tt []) => ();
+    (@raw_insert $StructName:ident $New:ident [$($table:tt)*]
+        [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident [$field_ty:ty]])*] [raw_insert]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                const _: () = {
+                    use $($table)* as table;
+
+                    // Deprecated fields are only moved, which shouldn't warn
+                    #[allow(dead_code, deprecated)]
+                    impl $New {
+                        /// `INSERT INTO table (columns) VALUES (placeholders)` for this struct
+                        /// as `DB` writes it, for when the insert has to be written as raw SQL,
+                        /// such as to add hints. Bind the values with `bind_raw_insert`
+                        pub fn raw_insert_sql<DB>() -> diesel::QueryResult<::std::string::String>
+                        where
+                            DB: diesel::backend::Backend,
+                            DB::QueryBuilder: ::core::default::Default,
+                            table::table: $crate::TableName<DB>,
+                        {
+                            $crate::raw::insert_sql::<table::table, DB>(
+                                &[$(<$crate::__diesel_new_column!(table $field_name $field_opts) as diesel::Column>::NAME),*],
+                            )
+                        }
+
+                        /// Binds the fields to a `sql_query` made from `raw_insert_sql`, in
+                        /// the order of the columns
+                        pub fn bind_raw_insert<'f, DB>(
+                            self,
+                            query: diesel::query_builder::SqlQuery,
+                        ) -> diesel::query_builder::BoxedSqlQuery<'f, DB, diesel::query_builder::SqlQuery>
+                        where
+                            DB: diesel::backend::Backend
+                                $(+ diesel::sql_types::HasSqlType<
+                                    <$crate::__diesel_new_column!(table $field_name $field_opts) as diesel::Expression>::SqlType,
+                                >)*,
+                            $(
+                                $field_ty: diesel::serialize::ToSql<
+                                    <$crate::__diesel_new_column!(table $field_name $field_opts) as diesel::Expression>::SqlType,
+                                    DB,
+                                > + ::core::marker::Send + 'f,
+                            )*
+                        {
+                            let query = query.into_boxed::<'f, DB>();
+                            $(
+                                let query = query.bind::<
+                                    <$crate::__diesel_new_column!(table $field_name $field_opts) as diesel::Expression>::SqlType,
+                                    _,
+                                >(self.$field_name);
+                            )*
+                            query
+                        }
+                    }
+                };
+            }
+        }
+    );
+
+    // The columns are named by Diesel, so that a renamed field is listed under
+    // the name of its column
+    (@skipped_columns $StructName:ident $New:ident $generics:tt [no_id] $opts:tt $table:tt) => (
+        $crate::diesel_new! { @skipped_columns $StructName $New $generics [] $opts $table }
+    );
+    (@skipped_columns $StructName:ident $New:ident $generics:tt [$id_opts:tt $id_meta:tt $id_vis:tt $id_name:ident $id_ty:tt] $opts:tt $table:tt) => (
+        $crate::diesel_new! { @skipped_columns $StructName $New $generics [[$id_opts $id_name]] $opts $table }
+    );
+    (@skipped_columns $StructName:ident $New:ident $generics:tt [$($columns:tt)*] [[skip $field_opts:tt $field_name:ident] $($opts:tt)*] $table:tt) => (
+        $crate::diesel_new! { @skipped_columns $StructName $New $generics [$($columns)* [$field_opts $field_name]] [$($opts)*] $table }
+    );
+    (@skipped_columns $StructName:ident $New:ident $generics:tt $columns:tt [$_opt:tt $($opts:tt)*] $table:tt) => (
+        $crate::diesel_new! { @skipped_columns $StructName $New $generics $columns [$($opts)*] $table }
+    );
+    (@skipped_columns $StructName:ident $New:ident [$([$($generics:tt)*] [$($param:tt)*])?] [$([$field_opts:tt $field_name:ident])*] [] [$($table:tt)*]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                const _: () = {
+                    #[allow(unused_imports)]
+                    use $($table)* as table;
+
+                    #[allow(dead_code)]
+                    impl $(<$($generics)*>)? $New $(<$($param),*>)? {
+                        /// The columns of the table that are left out of this struct, such
+                        /// as the `id`, which the database has to fill in with a default
+                        pub const SKIPPED_COLUMNS: &'static [&'static str] =
+                            &[$((<$crate::__diesel_new_column!(table $field_name $field_opts) as diesel::Column>::NAME)),*];
+                    }
+                };
+            }
+        }
+    );
+
+    (@has_new $StructName:ident $New:ident [$([$($generics:tt)*] [$($param:tt)*])?] [has_new]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                impl $(<$($generics)*>)? $crate::HasNewInsertable for $StructName $(<$($param),*>)? {
+                    type New = $New $(<$($param),*>)?;
+                }
+            }
+        }
+    );
+    // `diesel_new!` only generates the New structs, so there is no struct to
+    // implement it for
+    (@has_new $StructName:ident $New:ident $generics:tt $_fields:tt) => ();
+
+    // The fields marked with `#[new(natural_key)]` are collected as
+    // `[$field_opts $field_name [$new_ty] [$entity_ty]]`, where the type of the
+    // struct only differs with `#[new(type = ...)]`
+    (@natural_key $StructName:ident $New:ident $generics:tt $table:tt [] []) => ();
+    (@natural_key $StructName:ident $New:ident [$([$($generics:tt)*] [$($param:tt)*])?] [$($table:tt)*]
+        [$([$key_opts:tt $key_name:ident [$key_ty:ty] [$key_entity_ty:ty]])+] []
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                const _: () = {
+                    use $($table)* as table;
+
+                    #[allow(dead_code, deprecated)]
+                    impl $(<$($generics)*>)? $StructName $(<$($param),*>)? {
+                        /// The fields marked with `#[new(natural_key)]`, which identify the
+                        /// row as well as its id does
+                        pub fn natural_key(&self) -> ($(&$key_entity_ty,)+) {
+                            ($(&self.$key_name,)+)
+                        }
+                    }
+
+                    #[allow(dead_code, deprecated)]
+                    impl $(<$($generics)*>)? $New $(<$($param),*>)? {
+                        /// The fields marked with `#[new(natural_key)]`, for finding the row
+                        /// that this struct would insert
+                        pub fn natural_key(&self) -> ($(&$key_ty,)+) {
+                            ($(&self.$key_name,)+)
+                        }
+
+                        /// The columns of the natural key, such as for the conflict target
+                        /// of an upsert
+                        pub fn natural_key_columns() -> ($($crate::__diesel_new_column!(table $key_name $key_opts),)+) {
+                            ($($crate::__diesel_new_column!(table $key_name $key_opts),)+)
+                        }
+                    }
+                };
+            }
+        }
+    );
+    // Fields without any options can't be in the natural key
+    (@natural_key $StructName:ident $New:ident $generics:tt $table:tt $keys:tt [[[$([nullable])?] $($_field:tt)*] $($fields:tt)*]) => (
+        $crate::diesel_new! { @natural_key $StructName $New $generics $table $keys [$($fields)*] }
+    );
+    (@natural_key $StructName:ident $New:ident $generics:tt $table:tt $keys:tt
+        [[$field_opts:tt $_field_meta:tt $_field_vis:tt $field_name:ident [$field_ty:ty]] $($fields:tt)*]
+    ) => (
+        $crate::diesel_new! { @natural_key_field $StructName $New $generics $table $keys []
+            $field_opts [$field_opts $field_name [$field_ty] [$field_ty]] [$($fields)*]
+        }
+    );
+    (@natural_key_field $StructName:ident $New:ident $generics:tt $table:tt $keys:tt $_key:tt [[natural_key] $($opts:tt)*] $field:tt $fields:tt) => (
+        $crate::diesel_new! { @natural_key_field $StructName $New $generics $table $keys [natural_key] [$($opts)*] $field $fields }
+    );
+    (@natural_key_field $StructName:ident $New:ident $generics:tt $table:tt $keys:tt $key:tt [[entity_ty $entity_ty:ty] $($opts:tt)*]
+        [$field_opts:tt $field_name:ident $field_ty:tt $_entity_ty:tt] $fields:tt
+    ) => (
+        $crate::diesel_new! { @natural_key_field $StructName $New $generics $table $keys $key [$($opts)*]
+            [$field_opts $field_name $field_ty [$entity_ty]] $fields
+        }
+    );
+    (@natural_key_field $StructName:ident $New:ident $generics:tt $table:tt $keys:tt $key:tt [$_opt:tt $($opts:tt)*] $field:tt $fields:tt) => (
+        $crate::diesel_new! { @natural_key_field $StructName $New $generics $table $keys $key [$($opts)*] $field $fields }
+    );
+    (@natural_key_field $StructName:ident $New:ident $generics:tt $table:tt $keys:tt [] [] $_field:tt $fields:tt) => (
+        $crate::diesel_new! { @natural_key $StructName $New $generics $table $keys $fields }
+    );
+    (@natural_key_field $StructName:ident $New:ident $generics:tt $table:tt [$($keys:tt)*] [natural_key] [] $field:tt $fields:tt) => (
+        $crate::diesel_new! { @natural_key $StructName $New $generics $table [$($keys)* $field] $fields }
+    );
+
+    // `#[new(verify_schema)]` checks the columns of the New struct against the
+    // database at runtime, which `check_nullable` can only do against the `table!`
+    (@verify_schema $StructName:ident $New:ident $table:tt $fields:tt []) => ();
+    (@verify_schema $StructName:ident $New:ident [$($table:tt)*]
+        [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident $field_ty:tt])*] [verify_schema]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                const _: () = {
+                    use $($table)* as table;
+
+                    #[allow(dead_code)]
+                    impl $New {
+                        /// Checks that every column of this struct is in the table in the
+                        /// database, and is nullable exactly when its field is an `Option`,
+                        /// such as at startup to catch migrations that haven't been run
+                        pub fn verify_schema<Conn>(conn: &mut Conn) -> ::core::result::Result<(), $crate::schema::SchemaMismatch>
+                        where
+                            Conn: diesel::Connection,
+                            Conn::Backend: $crate::schema::SchemaBackend,
+                            <Conn::Backend as diesel::backend::Backend>::QueryBuilder: ::core::default::Default,
+                            table::table: $crate::TableName<Conn::Backend>,
+                            diesel::query_builder::SqlQuery:
+                                for<'query> diesel::query_dsl::LoadQuery<'query, Conn, $crate::schema::TableColumn>,
+                        {
+                            $crate::schema::verify::<table::table, Conn>(conn, &[$((
+                                <$crate::__diesel_new_column!(table $field_name $field_opts) as diesel::Column>::NAME,
+                                $crate::diesel_new!(@nullable $field_opts),
+                            )),*])
+                        }
+                    }
+                };
+            }
+        }
+    );
+
+    // `[nullable]` always comes first in the options of a field
+    (@nullable [[nullable] $($_opts:tt)*]) => (true);
+    (@nullable $_opts:tt) => (false);
+
+    (@from_form $StructName:ident $New:ident $fields:tt []) => ();
+    (@from_form $StructName:ident $New:ident $fields:tt [from_form]) => (
+        $crate::__diesel_new_form! { $StructName $New $fields }
+    );
+
+    (@graphql $struct_vis:tt $StructName:ident $New:ident $table:tt []) => ();
+    (@graphql $struct_vis:tt $StructName:ident $New:ident $table:tt [$pool:ty]) => (
+        $crate::__diesel_new_graphql! { $struct_vis $StructName $New $table [$pool] }
+    );
+
+    (@wasm []) => ();
+    (@wasm [wasm]) => (
+        $crate::__diesel_new_wasm! {}
+    );
+
+    (@proptest $StructName:ident $New:ident $table:tt $fields:tt []) => ();
+    (@proptest $StructName:ident $New:ident $table:tt $fields:tt [proptest]) => (
+        $crate::__diesel_new_proptest! { $StructName $New $table $fields }
+    );
+
+    (@dynamic $StructName:ident $New:ident $table:tt []) => ();
+    (@dynamic $StructName:ident $New:ident $table:tt [dynamic]) => (
+        $crate::__diesel_new_dynamic! { $StructName $New $table }
+    );
+
+    (@from_json $StructName:ident $New:ident $fields:tt []) => ();
+    (@from_json $StructName:ident $New:ident $fields:tt [from_json]) => (
+        $crate::__diesel_new_json! { $StructName $New $fields }
+    );
+
+    // `#[new(builder)]` generates a staged builder, where every field that isn't
+    // an `Option` has to be set in turn before getting to the last stage, which
+    // has setters for the `Option` fields and `build()`
+    (@builder $struct_vis:tt $StructName:ident $New:ident $fields:tt [$method:ident]) => (
+        $crate::diesel_new! { @builder_fields [] [] [] [] $fields [$struct_vis $StructName $New $method] }
+    );
+    // Each stage is generated once the required field after it is known, so
+    // that all of them take a single pass over the fields
+    (@builder_fields $first:tt $set:tt $pending:tt [$($optional:tt)*]
+        [[[[nullable] $($field_opts:tt)*] $($field:tt)*] $($fields:tt)*] $state:tt
+    ) => (
+        $crate::diesel_new! { @builder_fields $first $set $pending
+            [$($optional)* [[[nullable] $($field_opts)*] $($field)*]] [$($fields)*] $state
+        }
+    );
+    (@builder_fields [] [] [] $optional:tt [$field:tt $($fields:tt)*] $state:tt) => (
+        $crate::diesel_new! { @builder_fields [$field] [] [$field] $optional [$($fields)*] $state }
+    );
+    (@builder_fields $first:tt [$($set:tt)*] [$pending:tt] $optional:tt
+        [[$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident $field_ty:tt] $($fields:tt)*] $state:tt
+    ) => (
+        $crate::diesel_new! { @builder_stage $state [$($set)*] $pending [$field_name:camel] [] }
+        $crate::diesel_new! { @builder_fields $first [$($set)* $pending]
+            [[$field_opts $field_meta $field_vis $field_name $field_ty]] $optional [$($fields)*] $state
+        }
+    );
+    (@builder_fields [$first:tt] [$($set:tt)*] [$pending:tt] $optional:tt [] $state:tt) => (
+        $crate::diesel_new! { @builder_stage $state [$($set)*] $pending [] $optional }
+        $crate::diesel_new! { @builder_start $state [$first] $optional }
+        $crate::diesel_new! { @builder_build $state [$($set)* $pending] $optional }
+    );
+    (@builder_fields [] [] [] $optional:tt [] $state:tt) => (
+        $crate::diesel_new! { @builder_start $state [] $optional }
+        $crate::diesel_new! { @builder_build $state [] $optional }
+    );
+
+    // Without any required fields, the builder starts at the last stage
+    (@builder_start [$struct_vis:tt $StructName:ident $New:ident $method:ident] []
+        [$([$_optional_opts:tt $_optional_meta:tt $_optional_vis:tt $optional_name:ident $_optional_ty:tt])*]
+    ) => (
+        $crate::diesel_new! { @builder_start_stage $StructName $New $method [] [$($optional_name: ::core::option::Option::None,)*] }
+    );
+    (@builder_start [$struct_vis:tt $StructName:ident $New:ident $method:ident]
+        [[$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident $field_ty:tt]] $_optional:tt
+    ) => (
+        $crate::diesel_new! { @builder_start_stage $StructName $New $method [$field_name:camel] [] }
+    );
+    (@builder_start_stage $StructName:ident $New:ident $method:ident [$($stage:tt)*] [$($init:tt)*]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl $New {
+                    #[doc = "Starts building a [`" $New "`], one required field at a time"]
+                    pub fn $method() -> [< $New Builder $($stage)* >] {
+                        [< $New Builder $($stage)* >] { $($init)* }
+                    }
+                }
+            }
+        }
+    );
+
+    (@builder_stage [[$struct_vis:vis] $StructName:ident $New:ident $_method:ident]
+        [$([$_set_opts:tt $_set_meta:tt $_set_vis:tt $set_name:ident [$set_ty:ty]])*]
+        [$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident [$field_ty:ty]]
+        [$($next:tt)*]
+        [$([$_optional_opts:tt $_optional_meta:tt $_optional_vis:tt $optional_name:ident $_optional_ty:tt])*]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[doc = "Stage of building a [`" $New "`] which needs `" $field_name "` next"]
+                #[must_use]
+                $struct_vis struct [< $New Builder $field_name:camel >] {
+                    $($set_name: $set_ty,)*
+                }
+
+                #[allow(dead_code, deprecated)]
+                impl [< $New Builder $field_name:camel >] {
+                    #[doc = "Sets `" $field_name "`"]
+                    pub fn $field_name(
+                        self,
+                        $field_name: impl ::core::convert::Into<$field_ty>,
+                    ) -> [< $New Builder $($next)* >] {
+                        [< $New Builder $($next)* >] {
+                            $($set_name: self.$set_name,)*
+                            $field_name: $field_name.into(),
+                            $($optional_name: ::core::option::Option::None,)*
+                        }
+                    }
+                }
+            }
+        }
+    );
+
+    (@builder_build [[$struct_vis:vis] $StructName:ident $New:ident $_method:ident]
+        [$([$required_opts:tt $required_meta:tt $required_vis:tt $required_name:ident [$required_ty:ty]])*]
+        [$([$optional_opts:tt $optional_meta:tt $optional_vis:tt $optional_name:ident [$optional_ty:ty]])*]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[doc = "Last stage of building a [`" $New "`], where the optional fields can be set"]
+                #[must_use]
+                $struct_vis struct [< $New Builder >] {
+                    $($required_name: $required_ty,)*
+                    $($optional_name: $optional_ty,)*
+                }
+
+                #[allow(dead_code, deprecated)]
+                impl [< $New Builder >] {
+                    $(
+                        #[doc = "Sets `" $optional_name "`"]
+                        pub fn $optional_name(mut self, $optional_name: impl ::core::convert::Into<$optional_ty>) -> Self {
+                            self.$optional_name = $optional_name.into();
+                            self
+                        }
+                    )*
+
+                    #[doc = "Builds the [`" $New "`]"]
+                    pub fn build(self) -> $New {
+                        $New {
+                            $($required_name: self.$required_name,)*
+                            $($optional_name: self.$optional_name,)*
+                        }
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(check_id)]` fails to compile when the id isn't an integer (or a
+    // newtype around one), as nothing else is generated by the database
+    (@check_id $id:tt []) => ();
+    (@check_id [no_id] [check_id]) => (
+        ::core::compile_error!("`#[new(check_id)]` needs an `id` field, which `#[new(no_id)]` leaves out");
+    );
+    (@check_id [$id_opts:tt $id_meta:tt $id_field_vis:tt $_id_name:ident [$id_type:ty]] [check_id]) => (
+        const _: () = {
+            #[allow(dead_code)]
+            fn autoincrement_id<Id: $crate::AutoincrementId>() {}
+
+            #[allow(dead_code)]
+            fn check_id() {
+                autoincrement_id::<$id_type>();
+            }
+        };
+    );
+
+    // `#[new(check_for_backend(...))]` checks that the New struct can be
+    // inserted with each backend, which is what Diesel's own
+    // `check_for_backend` does for `Selectable`
+    (@check_for_backend $StructName:ident $New:ident $table:tt []) => ();
+    (@check_for_backend $StructName:ident $New:ident [$($table:tt)*] [$($backend:path),+]) => (
+        $crate::paste! {
+            const _: () = {
+                #[allow(dead_code)]
+                fn insertable<Backend>()
+                where
+                    Backend: diesel::backend::Backend,
+                    <$New as diesel::Insertable<$($table)*::table>>::Values:
+                        diesel::query_builder::QueryFragment<Backend>,
+                {
+                }
+
+                #[allow(dead_code)]
+                fn check_for_backend() {
+                    $(insertable::<$backend>();)+
+                }
+            };
+        }
+    );
+
+    // `#[new(display)]` prints `table(field=value, ...)` with the `Debug` of
+    // each value, or `***` for fields marked with `#[new(redact)]`
+    (@display $StructName:ident $New:ident $table:tt $_last:tt $fields:tt []) => ();
+    (@display $StructName:ident $New:ident $table:tt [$_segment:ident :: $($last:tt)+] $fields:tt [display]) => (
+        $crate::diesel_new! { @display $StructName $New $table [$($last)+] $fields [display] }
+    );
+    (@display $StructName:ident $New:ident $table:tt [$table_name:tt]
+        [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident $field_ty:tt])*] [display]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(deprecated)]
+                impl ::core::fmt::Display for $New {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        let fields: &[(&str, ::core::option::Option<&dyn ::core::fmt::Debug>)] = &[
+                            $((
+                                ::core::stringify!($field_name),
+                                $crate::__diesel_new_redact! { $field_opts [self.$field_name] },
+                            ),)*
+                        ];
+
+                        ::core::write!(f, "{}(", ::core::stringify!($table_name))?;
+                        for (i, (name, value)) in fields.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str(", ")?;
+                            }
+                            match value {
+                                ::core::option::Option::Some(value) => ::core::write!(f, "{}={:?}", name, value)?,
+                                ::core::option::Option::None => ::core::write!(f, "{}=***", name)?,
+                            }
+                        }
+                        f.write_str(")")
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(create)]` inserts with a `RETURNING` clause. Rather than being gated
+    // on features for each backend, it is bound on Diesel's `LoadQuery`, which
+    // only holds for backends (and Diesel features) with support for it
+    (@create $StructName:ident $New:ident [$($table:tt)*] [$method:ident]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl $New {
+                    #[doc = ::core::concat!(
+                        "Inserts the row and returns it as a [`", ::core::stringify!($StructName), "`]",
+                    )]
+                    ///
+                    /// This needs a backend with support for `RETURNING` clauses,
+                    /// which for SQLite is behind Diesel's
+                    /// `returning_clauses_for_sqlite_3_35` feature.
+                    pub fn $method<'query, Conn>(self, conn: &mut Conn) -> diesel::QueryResult<$StructName>
+                    where
+                        Conn: diesel::Connection,
+                        $($table)*::table: $crate::InstrumentedTable<Conn::Backend>,
+                        diesel::query_builder::InsertStatement<
+                            $($table)*::table,
+                            <Self as diesel::Insertable<$($table)*::table>>::Values,
+                        >: diesel::query_dsl::LoadQuery<'query, Conn, $StructName>,
+                    {
+                        $crate::instrument::insert::<$($table)*::table, Conn::Backend, _>(|| {
+                            diesel::RunQueryDsl::get_result(diesel::insert_into($($table)*::table).values(self), conn)
+                        })
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(retry)]` inserts in a transaction of its own, which is tried again
+    // on serialization failures and deadlocks
+    (@retry $StructName:ident $New:ident [$($table:tt)*] [$method:ident]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl $New {
+                    #[doc = ::core::concat!(
+                        "Inserts the row and returns it as a [`", ::core::stringify!($StructName),
+                        "`], trying again as long as the policy allows when the transaction ",
+                        "fails with a serialization failure or a deadlock",
+                    )]
+                    ///
+                    /// This has to be called outside of any other transaction, and
+                    /// needs a backend with support for `RETURNING` clauses.
+                    pub fn $method<'query, Conn>(
+                        &'query self,
+                        conn: &mut Conn,
+                        policy: &$crate::RetryPolicy,
+                    ) -> diesel::QueryResult<$StructName>
+                    where
+                        Conn: diesel::Connection,
+                        $($table)*::table: $crate::InstrumentedTable<Conn::Backend>,
+                        diesel::query_builder::InsertStatement<
+                            $($table)*::table,
+                            <&'query Self as diesel::Insertable<$($table)*::table>>::Values,
+                        >: diesel::query_dsl::LoadQuery<'query, Conn, $StructName>,
+                    {
+                        $crate::instrument::insert::<$($table)*::table, Conn::Backend, _>(|| {
+                            $crate::with_retry(conn, policy, |conn| {
+                                diesel::RunQueryDsl::get_result(diesel::insert_into($($table)*::table).values(self), conn)
+                            })
+                        })
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(with_id)]` generates a `{Struct}WithId` with the id as well, for
+    // imports that have to keep the ids of the rows. Unlike the New structs it
+    // has every column, so derives such as `Queryable` work on it too. Like the
+    // New structs it doesn't derive `Identifiable`, which would implement
+    // `HasTable` a second time
+    (@with_id $meta:tt $struct_vis:tt $StructName:ident [[no_id] $fields:tt] $table:tt $_method:tt) => (
+        ::core::compile_error!("`#[new(with_id)]` needs an `id` field, which `#[new(no_id)]` leaves out");
+    );
+    (@with_id $meta:tt $struct_vis:tt $StructName:ident $fields:tt $table:tt $method:tt) => (
+        $crate::diesel_new! { @with_id [] $meta [$struct_vis $StructName $fields $table $method] }
+    );
+    (@with_id $kept:tt [[derive($($paths:tt)*)] $($meta:tt)*] $state:tt) => (
+        $crate::diesel_new! { @identifiable [] [$($paths)*] [with_id $kept [$($meta)*] $state] }
+    );
+    (@with_id [$($kept:tt)*] [$attr:tt $($meta:tt)*] $state:tt) => (
+        $crate::diesel_new! { @with_id [$($kept)* $attr] [$($meta)*] $state }
+    );
+    (@with_id [$($meta:tt)*] [] [$struct_vis:tt $StructName:ident [$id:tt [$($fields:tt)*]] [$($table:tt)*] [$method:ident]]) => (
+        $crate::diesel_new! {
+            @struct $StructName [
+                [doc = ::core::concat!(
+                    "A [`", ::core::stringify!($StructName), "`] with its id, for inserting rows that keep their ids"
+                )]
+                [doc = ""]
+                $($meta)*
+            ]
+            $struct_vis [$StructName WithId] [] [$id $($fields)*] [$($table)*] [#[derive(diesel::Insertable)]]
+        }
+
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl [< $StructName WithId >] {
+                    /// Inserts the row with the id it has, such as when restoring a
+                    /// backup or copying rows from another database, and returns the
+                    /// number of rows inserted
+                    ///
+                    /// Postgres doesn't advance the sequence of the id for inserted
+                    /// ids, so it has to be reset with `setval` after an import.
+                    pub fn $method<Conn>(self, conn: &mut Conn) -> diesel::QueryResult<usize>
+                    where
+                        Conn: diesel::Connection,
+                        $($table)*::table: $crate::InstrumentedTable<Conn::Backend>,
+                        diesel::query_builder::InsertStatement<
+                            $($table)*::table,
+                            <Self as diesel::Insertable<$($table)*::table>>::Values,
+                        >: diesel::query_dsl::methods::ExecuteDsl<Conn>,
+                    {
+                        $crate::InsertNew::insert(self, conn)
+                    }
+                }
+            }
+        }
+    );
+
+    (@update_from_new $StructName:ident $New:ident [no_id] $table:tt $_method:tt) => (
+        ::core::compile_error!("`#[new(update_from_new)]` needs an `id` field, which `#[new(no_id)]` leaves out");
+    );
+    (@update_from_new $StructName:ident $New:ident [$id_opts:tt $id_meta:tt $id_field_vis:tt $_id_name:ident [$id_type:ty]] [$($table:tt)*] [$method:ident]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl $StructName {
+                    #[doc = ::core::concat!(
+                        "Replaces every column of the row with the given id with the values of a [`",
+                        ::core::stringify!($New),
+                        "`] and returns the updated row",
+                    )]
+                    pub fn $method<'query, Conn>(
+                        id: $id_type,
+                        new: $New,
+                        conn: &mut Conn,
+                    ) -> diesel::QueryResult<Self>
+                    where
+                        diesel::dsl::Update<diesel::dsl::Find<$($table)*::table, $id_type>, $New>:
+                            diesel::query_dsl::LoadQuery<'query, Conn, Self>,
+                    {
+                        diesel::RunQueryDsl::get_result(
+                            diesel::update(diesel::QueryDsl::find($($table)*::table, id)).set(new),
+                            conn,
+                        )
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(conversions)]` converts between the struct and its New struct by
+    // moving each field, through `Into` for fields with another type on the New
+    // struct. Skipped fields can't be filled in from the New struct, unlike
+    // `#[new(default)]` fields, which get the same value as when inserted
+    (@conversions $_opts:tt $_defaults:tt $StructName:ident $New:ident [no_id] $fields:tt $_method:tt) => (
+        ::core::compile_error!("`#[new(conversions)]` needs an `id` field, which `#[new(no_id)]` leaves out");
+    );
+    (@conversions [[skip $field_opts:tt $field_name:ident] $($_opts:tt)*] $($_rest:tt)*) => (
+        ::core::compile_error!(::core::concat!(
+            "`#[new(conversions)]` can't fill in `", ::core::stringify!($field_name), "`, which is left out by `#[new(skip)]`"
+        ));
+    );
+    (@conversions [[default $_meta:tt $field_name:ident $_ty:tt $value:tt] $($opts:tt)*] [$($defaults:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @conversions [$($opts)*] [$($defaults)* [$field_name $value]] $($rest)* }
+    );
+    (@conversions [$_opt:tt $($opts:tt)*] $($rest:tt)*) => (
+        $crate::diesel_new! { @conversions [$($opts)*] $($rest)* }
+    );
+    (@conversions [] [$([$default_name:ident [$default:expr]])*] $StructName:ident $New:ident [$id_opts:tt $id_meta:tt $id_field_vis:tt $id_name:ident [$id_type:ty]]
+        [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident $field_ty:tt])*] [$method:ident]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                // Deprecated fields are only moved, which shouldn't warn
+                #[allow(deprecated, clippy::useless_conversion)]
+                impl ::core::convert::From<$StructName> for $New {
+                    fn from(row: $StructName) -> Self {
+                        Self {
+                            $($field_name: ::core::convert::Into::into(row.$field_name),)*
+                        }
+                    }
+                }
+
+                #[allow(dead_code, deprecated, clippy::useless_conversion)]
+                impl $StructName {
+                    #[doc = "Creates the row that inserting a [`" $New "`] returned the given id for"]
+                    pub fn $method(id: $id_type, new: $New) -> Self {
+                        Self {
+                            $id_name: id,
+                            $($field_name: ::core::convert::Into::into(new.$field_name),)*
+                            $($default_name: $default,)*
+                        }
+                    }
+                }
+            }
+        }
+    );
+
+    // `#[new(event)]` mirrors the New struct and the id returned by the insert
+    // into a serializable event. Only docs and serde attributes are copied over
+    (@event $struct_vis:tt $StructName:ident $New:ident $id:tt $fields:tt []) => ();
+    (@event $struct_vis:tt $StructName:ident $New:ident [no_id] $fields:tt [event]) => (
+        ::core::compile_error!("`#[new(event)]` needs an `id` field, which `#[new(no_id)]` leaves out");
+    );
+    (@event $struct_vis:tt $StructName:ident $New:ident [$id_opts:tt [$($id_meta:tt)*] $($id:tt)*] [$($fields:tt)*] [event]) => (
+        $crate::__diesel_new_event! { @event_field [$struct_vis $StructName $New] [] [] [$($id_meta)*] [$($id)*] [$($fields)*] }
+    );
+    // The rest of the attributes being docs, which is the usual case, the field
+    // is done in the same step
+    (@event_field $event:tt [$($done:tt)*] [$($kept:tt)*] [$(#[doc $($doc:tt)*])*] [$_vis:tt $field_name:ident $field_ty:tt]
+        [[$_opts:tt [$($meta:tt)*] $($field:tt)*] $($fields:tt)*]
+    ) => (
+        $crate::diesel_new! { @event_field $event [$($done)* [[$($kept)* $(#[doc $($doc)*])*] $field_name $field_ty]] []
+            [$($meta)*] [$($field)*] [$($fields)*]
+        }
+    );
+    (@event_field $event:tt [$($done:tt)*] [$($kept:tt)*] [$(#[doc $($doc:tt)*])*] [$_vis:tt $field_name:ident $field_ty:tt] []) => (
+        $crate::diesel_new! { @event_struct $event [$($done)* [[$($kept)* $(#[doc $($doc)*])*] $field_name $field_ty]] }
+    );
+    (@event_field $event:tt $done:tt [$($kept:tt)*] [#[doc $($doc:tt)*] $($meta:tt)*] $field:tt $fields:tt) => (
+        $crate::diesel_new! { @event_field $event $done [$($kept)* #[doc $($doc)*]] [$($meta)*] $field $fields }
+    );
+    (@event_field $event:tt $done:tt [$($kept:tt)*] [#[serde $($serde:tt)*] $($meta:tt)*] $field:tt $fields:tt) => (
+        $crate::diesel_new! { @event_field $event $done [$($kept)* #[serde $($serde)*]] [$($meta)*] $field $fields }
+    );
+    (@event_field $event:tt $done:tt $kept:tt [#[$($_attr:tt)*] $($meta:tt)*] $field:tt $fields:tt) => (
+        $crate::diesel_new! { @event_field $event $done $kept [$($meta)*] $field $fields }
+    );
+    (@event_struct [[$struct_vis:vis] $StructName:ident $New:ident]
+        [[[$($id_meta:tt)*] $id_name:ident [$id_type:ty]] $([[$($field_meta:tt)*] $field_name:ident [$field_ty:ty]])*]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[doc = ::core::concat!("Event for a [`", ::core::stringify!($StructName), "`] having been created")]
+                #[derive(Debug, Clone, $crate::serde::Serialize, $crate::serde::Deserialize)]
+                #[serde(crate = "diesel_autoincrement_new_struct::serde")]
+                $struct_vis struct [< $StructName CreatedEvent >] {
+                    $($id_meta)*
+                    pub $id_name: $id_type,
+                    $(
+                        $($field_meta)*
+                        pub $field_name: $field_ty,
+                    )*
+                }
+
+                // Deprecated fields are only moved, which shouldn't warn
+                #[allow(dead_code, deprecated)]
+                impl [< $StructName CreatedEvent >] {
+                    /// Creates the event from the id returned by the insert and the New
+                    /// struct that was inserted
+                    pub fn new(id: $id_type, new: $New) -> Self {
+                        Self {
+                            $id_name: id,
+                            $($field_name: new.$field_name,)*
+                        }
+                    }
+                }
+            }
+        }
+    );
+
+    // With `#[new(create)]` as well, the event is returned along with the row,
+    // so that it can be written to an outbox in the same transaction
+    (@event_create $opts:tt $StructName:ident $New:ident $id:tt $table:tt []) => ();
+    (@event_create $opts:tt $StructName:ident $New:ident $id:tt $table:tt [event]) => (
+        $crate::__diesel_new_flag! { create $opts [create] []
+            [$crate::diesel_new] { @create_with_event $StructName $New $id $table }
+        }
+    );
+    (@create_with_event $StructName:ident $New:ident $id:tt $table:tt []) => ();
+    (@create_with_event $StructName:ident $New:ident [no_id] $table:tt [create]) => (
+        ::core::compile_error!("`create_with_event` needs an `id` field, which `#[new(no_id)]` leaves out");
+    );
+    (@create_with_event $StructName:ident $New:ident [$id_opts:tt $id_meta:tt $id_vis:tt $id_name:ident [$id_type:ty]] [$($table:tt)*] [create]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl $New {
+                    #[doc = ::core::concat!(
+                        "Inserts the row and returns it as a [`", ::core::stringify!($StructName),
+                        "`], along with the [`", ::core::stringify!($StructName), "CreatedEvent`] for it",
+                    )]
+                    ///
+                    /// Call it in a transaction to write the event to an outbox table
+                    /// along with the row. The New struct has to be `Clone`, such as
+                    /// with `#[new(derive(Clone, ...))]`, as one copy is inserted and
+                    /// the other goes into the event, and like
+                    /// `create` this needs a backend with support for `RETURNING`
+                    /// clauses.
+                    pub fn create_with_event<'query, Conn>(
+                        self,
+                        conn: &mut Conn,
+                    ) -> diesel::QueryResult<($StructName, [< $StructName CreatedEvent >])>
+                    where
+                        Conn: diesel::Connection,
+                        $($table)*::table: $crate::InstrumentedTable<Conn::Backend>,
+                        diesel::query_builder::InsertStatement<
+                            $($table)*::table,
+                            <Self as diesel::Insertable<$($table)*::table>>::Values,
+                        >: diesel::query_dsl::LoadQuery<'query, Conn, $StructName>,
+                    {
+                        let row: $StructName = $crate::instrument::insert::<$($table)*::table, Conn::Backend, _>(|| {
+                            diesel::RunQueryDsl::get_result(
+                                diesel::insert_into($($table)*::table).values($crate::event::CloneForEvent::clone_for_event(&self)),
+                                conn,
+                            )
+                        })?;
+                        let event = [< $StructName CreatedEvent >]::new(::core::clone::Clone::clone(&row.$id_name), self);
+
+                        ::core::result::Result::Ok((row, event))
+                    }
+                }
+            }
+        }
+    );
+
+    (@struct $StructName:ident $meta:tt $struct_vis:tt $new_name:tt $generics:tt $fields:tt $table:tt [@defaults $defaults:tt]) => (
+        $crate::diesel_new! { @struct $StructName $meta $struct_vis $new_name $generics $fields $table [] }
+        $crate::diesel_new! { @with_defaults $StructName $new_name $table [] [] $fields $defaults }
+    );
+    (@struct $StructName:ident
+        [$([$($struct_meta:tt)*])*]
+        [$struct_vis:vis] [$($new_name:tt)*] [$([$($generics:tt)*] [$($param:tt)*])?]
+        [$([$field_opts:tt [$($field_meta:tt)*] [$field_vis:vis] $field_name:ident [$field_ty:ty]])*]
+        [$($table:tt)*] [$($derive_insertable:tt)*]
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                $(#[$($struct_meta)*])*
+                $($derive_insertable)*
+                $struct_vis struct [< $($new_name)* >] $(<$($generics)*>)? {
+                    $(
+                        $($field_meta)*
+                        $field_vis $field_name: $field_ty,
+                    )*
+                }
+
+                // Lets the blanket impl of `InsertNew` know which table to insert into
+                impl $(<$($generics)*>)? diesel::associations::HasTable for [< $($new_name)* >] $(<$($param),*>)? {
+                    type Table = $($table)*::table;
+
+                    fn table() -> Self::Table {
+                        $($table)*::table
+                    }
+                }
+            }
+        }
+    );
+
+    // The fields of the New struct and the `#[new(default)]` fields are inserted
+    // through a struct with both, which derives `Insertable`. Only the
+    // `#[diesel(...)]` attributes of the fields are kept on it, and it is `pub`
+    // so that it can be named in `Values`, but can't be reached outside of the
+    // `const`
+    (@with_defaults $StructName:ident $new_name:tt $table:tt $done:tt $done_defaults:tt
+        [[$_opts:tt [$($field_meta:tt)*] $_vis:tt $field_name:ident [$field_ty:ty]] $($fields:tt)*] $defaults:tt
+    ) => (
+        $crate::diesel_new! { @diesel_attrs [] [$($field_meta)*]
+            [field $field_name [$field_ty] [@with_defaults $StructName $new_name $table $done $done_defaults [$($fields)*] $defaults]]
+        }
+    );
+    (@with_defaults $StructName:ident $new_name:tt $table:tt $done:tt $done_defaults:tt []
+        [[[$($field_meta:tt)*] $field_name:ident [$field_ty:ty] $value:tt] $($defaults:tt)*]
+    ) => (
+        $crate::diesel_new! { @diesel_attrs [] [$($field_meta)*]
+            [default $field_name [$field_ty] $value [@with_defaults $StructName $new_name $table $done $done_defaults [] [$($defaults)*]]]
+        }
+    );
+    (@with_defaults $StructName:ident [$($new_name:tt)*] [$($table:tt)*]
+        [$([[$($field_meta:tt)*] $field_name:ident [$field_ty:ty]])*]
+        [$([[$($default_meta:tt)*] $default_name:ident [$default_ty:ty] [$default:expr]])*] [] []
+    ) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                const _: () = {
+                    #[derive(diesel::Insertable)]
+                    #[diesel(table_name = $($table)*)]
+                    #[allow(deprecated)]
+                    pub struct WithDefaults {
+                        $($($field_meta)* $field_name: $field_ty,)*
+                        $($($default_meta)* $default_name: $default_ty,)*
+                    }
+
+                    #[allow(deprecated)]
+                    impl diesel::Insertable<$($table)*::table> for [< $($new_name)* >] {
+                        type Values = <WithDefaults as diesel::Insertable<$($table)*::table>>::Values;
+
+                        fn values(self) -> Self::Values {
+                            diesel::Insertable::values(WithDefaults {
+                                $($field_name: self.$field_name,)*
+                                $($default_name: $default,)*
+                            })
+                        }
+                    }
+
+                    // What lets a `Vec` of them be inserted at once, as the derive does
+                    impl diesel::internal::derives::insertable::UndecoratedInsertRecord<$($table)*::table>
+                        for [< $($new_name)* >]
+                    {
+                    }
+                };
+            }
+        }
+    );
+
+    (@diesel_attrs [$($kept:tt)*] [#[diesel $diesel:tt] $($meta:tt)*] $k:tt) => (
+        $crate::diesel_new! { @diesel_attrs [$($kept)* #[diesel $diesel]] [$($meta)*] $k }
+    );
+    (@diesel_attrs $kept:tt [#[$($_attr:tt)*] $($meta:tt)*] $k:tt) => (
+        $crate::diesel_new! { @diesel_attrs $kept [$($meta)*] $k }
+    );
+    (@diesel_attrs $kept:tt [] [field $field_name:ident $field_ty:tt [@with_defaults $StructName:ident $new_name:tt $table:tt [$($done:tt)*] $($rest:tt)*]]) => (
+        $crate::diesel_new! { @with_defaults $StructName $new_name $table [$($done)* [$kept $field_name $field_ty]] $($rest)* }
+    );
+    (@diesel_attrs $kept:tt [] [default $field_name:ident $field_ty:tt $value:tt
+        [@with_defaults $StructName:ident $new_name:tt $table:tt $done:tt [$($done_defaults:tt)*] $($rest:tt)*]
+    ]) => (
+        $crate::diesel_new! { @with_defaults $StructName $new_name $table $done [$($done_defaults)* [$kept $field_name $field_ty $value]] $($rest)* }
+    );
+
+    ($($item:tt)*) => (
+        $crate::diesel_new! { @attrs [new] [] [] $($item)* }
+    );
+}
+
+/// Macro to snapshot the fields of the New struct that would be generated
+///
+/// Takes the same input as [diesel_new] and expands to a
+/// `&[(&str, &str)]` of the generated field names and types, so that a test
+/// can catch the shape of the generated struct silently changing between
+/// versions of this crate.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::snapshot_new_struct;
+///
+/// const NEW_USER: &[(&str, &str)] = snapshot_new_struct! {
+///     #[derive(Debug, Clone, Queryable, AsChangeset)]
+///     #[diesel(table_name = users)]
+///     pub struct User {
+///         id: i32,
+///         name: String,
+///         email: Option<String>,
+///     }
+/// };
+///
+/// assert_eq!(NEW_USER, &[("name", "String"), ("email", "Option<String>")]);
+/// ```
+#[macro_export]
+macro_rules! snapshot_new_struct {( $($item:tt)* ) => (
+    $crate::diesel_new! { @attrs [snapshot] [] [] $($item)* }
+)}
+
+/// Macro to apply [NewInsertable] to every struct in a module
+///
+/// Takes an inline module, or the items that would go in one, and generates
+/// the New structs of each struct with named fields directly inside it, as if
+/// each of them had `#[apply(NewInsertable!)]`. Everything else in the module
+/// is left as it is, as are structs marked `#[new(ignore)]`, such as view
+/// models that aren't backed by a table. `ignore` can be given along with
+/// other options, and every `#[new(...)]` on an ignored struct is removed.
+///
+/// Each struct adds to how deeply the macros are expanded, so modules with a
+/// lot of them may need a higher `#![recursion_limit]` in the crate.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::diesel_new_all;
+///
+/// diesel_new_all! {
+///     pub mod models {
+///         use diesel::prelude::*;
+///
+///         table! {
+///             users(id) {
+///                 id -> Integer,
+///                 name -> Text,
+///             }
+///         }
+///
+///         table! {
+///             posts(id) {
+///                 id -> Integer,
+///                 title -> Text,
+///             }
+///         }
+///
+///         #[derive(Debug, Clone, Queryable)]
+///         #[diesel(table_name = users)]
+///         pub struct User {
+///             pub id: i32,
+///             pub name: String,
+///         }
+///
+///         #[derive(Debug, Clone, Queryable)]
+///         #[diesel(table_name = posts)]
+///         pub struct Post {
+///             pub id: i32,
+///             pub title: String,
+///         }
+///
+///         #[new(ignore)]
+///         pub struct UserWithPosts {
+///             pub user: User,
+///             pub posts: Vec<Post>,
+///         }
+///     }
+/// }
+///
+/// let _ = models::NewUser { name: String::from("Ferris") };
+/// let _ = models::NewPost { title: String::from("Hello") };
+/// ```
+#[macro_export]
+macro_rules! diesel_new_all {
+    ($(#[$($mod_meta:tt)*])* $vis:vis mod $name:ident { $($items:tt)* }) => (
+        $(#[$($mod_meta)*])*
+        $vis mod $name {
+            $crate::diesel_new_all! { @items $($items)* }
+        }
+    );
+
+    (@items) => ();
+    // The struct is handed on as soon as its body is found, so that each one
+    // only adds a single step to the walk over the items
+    (@items $(#[$($meta:tt)*])* $vis:vis struct $StructName:ident { $($body:tt)* } $($rest:tt)*) => (
+        $crate::diesel_new_all! { @struct [] [] [] [$([$($meta)*])*] [$vis struct $StructName] { $($body)* } }
+        $crate::diesel_new_all! { @items $($rest)* }
+    );
+    (@items $(#[$($meta:tt)*])* $vis:vis struct $StructName:ident $($rest:tt)*) => (
+        $crate::diesel_new_all! { @head [$([$($meta)*])*] [$vis struct $StructName] $($rest)* }
+    );
+    (@items $item:item $($rest:tt)*) => (
+        $item
+        $crate::diesel_new_all! { @items $($rest)* }
+    );
+
+    // Generics and `where` clauses come before the body of the struct, while
+    // tuple and unit structs end in a `;` and are left as they are
+    (@head $meta:tt $head:tt { $($body:tt)* } $($rest:tt)*) => (
+        $crate::diesel_new_all! { @struct [] [] [] $meta $head { $($body)* } }
+        $crate::diesel_new_all! { @items $($rest)* }
+    );
+    (@head [$([$($meta:tt)*])*] [$($head:tt)*] ; $($rest:tt)*) => (
+        $(#[$($meta)*])*
+        $($head)*;
+        $crate::diesel_new_all! { @items $($rest)* }
+    );
+    (@head $meta:tt [$($head:tt)*] $token:tt $($rest:tt)*) => (
+        $crate::diesel_new_all! { @head $meta [$($head)* $token] $($rest)* }
+    );
+
+    // Every attribute of the struct is kept for `NewInsertable!`, and all but the
+    // `#[new(...)]` ones for structs that are `ignore`d, as nothing else would
+    // make sense of them
+    (@struct [$($all:tt)*] $kept:tt $ignored:tt [[new $opts:tt] $($metas:tt)*] $head:tt $body:tt) => (
+        $crate::diesel_new_all! { @ignore $opts [[$($all)* [new $opts]] $kept $ignored [$($metas)*] $head $body] }
+    );
+    (@struct [$($all:tt)*] [$($kept:tt)*] $ignored:tt [$meta:tt $($metas:tt)*] $head:tt $body:tt) => (
+        $crate::diesel_new_all! { @struct [$($all)* $meta] [$($kept)* $meta] $ignored [$($metas)*] $head $body }
+    );
+    (@struct $all:tt [$([$($kept:tt)*])*] [ignore] [] [$($head:tt)*] $body:tt) => (
+        $(#[$($kept)*])*
+        $($head)* $body
+    );
+    (@struct [$([$($meta:tt)*])*] $kept:tt [] [] [$($head:tt)*] $body:tt) => (
+        $crate::NewInsertable! {
+            $(#[$($meta)*])*
+            $($head)* $body
+        }
+    );
+
+    // `ignore` can be any of the options of any `#[new(...)]` on the struct
+    (@ignore ($($opts:tt)*) $state:tt) => (
+        $crate::diesel_new_all! { @ignore_opt [$($opts)*] $state }
+    );
+    (@ignore_opt [ignore $(, $($_opts:tt)*)?] [$all:tt $kept:tt $_ignored:tt $($state:tt)*]) => (
+        $crate::diesel_new_all! { @struct $all $kept [ignore] $($state)* }
+    );
+    (@ignore_opt [] [$($state:tt)*]) => (
+        $crate::diesel_new_all! { @struct $($state)* }
+    );
+    (@ignore_opt [$_opt:tt $($opts:tt)*] $state:tt) => (
+        $crate::diesel_new_all! { @ignore_next [$($opts)*] $state }
+    );
+    (@ignore_next [, $($opts:tt)*] $state:tt) => (
+        $crate::diesel_new_all! { @ignore_opt [$($opts)*] $state }
+    );
+    (@ignore_next [$_token:tt $($opts:tt)*] $state:tt) => (
+        $crate::diesel_new_all! { @ignore_next [$($opts)*] $state }
+    );
+    (@ignore_next [] [$($state:tt)*]) => (
+        $crate::diesel_new_all! { @struct $($state)* }
+    );
+
+    ($($items:tt)*) => (
+        $crate::diesel_new_all! { @items $($items)* }
+    );
+}
+
+/// Macro to verify that a New struct round-trips through an in-memory SQLite database
+///
+/// The table is created from its `table!` definition, the New struct is
+/// inserted and the row is read back as the entity, and every field given in
+/// the New struct is asserted to be equal between the two. This catches drift
+/// between structs and tables which compiles fine but fails at runtime.
+///
+/// Requires the `test-support` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::{apply, NewInsertable, verify_insert_roundtrip};
+/// use diesel::prelude::*;
+///
+/// table! {
+///     users(id) {
+///         id -> Integer,
+///         name -> Text,
+///     }
+/// }
+///
+/// #[apply(NewInsertable!)]
+/// #[derive(Debug, Clone, Queryable)]
+/// #[diesel(table_name = users)]
+/// pub struct User {
+///     id: i32,
+///     name: String
+/// }
+///
+/// verify_insert_roundtrip!(User, NewUser { name: String::from("Ferris") }, users::table);
+/// ```
+#[cfg(feature = "test-support")]
+#[macro_export]
+macro_rules! verify_insert_roundtrip {
+    ($Entity:ty, $($NewStruct:ident)::+ { $($field:ident : $value:expr),* $(,)? }, $table:expr $(,)?) => {{
+        let mut conn = <diesel::sqlite::SqliteConnection as diesel::Connection>::establish(":memory:")
+            .expect("failed to open an in-memory SQLite database");
+
+        $crate::test_support::create_sqlite_table($table, &mut conn)
+            .expect("failed to create the table from its schema");
+
+        let new = $($NewStruct)::+ { $($field: $value),* };
+
+        diesel::RunQueryDsl::execute(diesel::insert_into($table).values(&new), &mut conn)
+            .expect("failed to insert the New struct");
+
+        let entity: $Entity = diesel::RunQueryDsl::first($table, &mut conn)
+            .expect("failed to read the entity back");
+
+        $(
+            assert_eq!(
+                entity.$field, new.$field,
+                ::core::concat!("`", ::core::stringify!($field), "` did not round-trip")
+            );
+        )*
+    }};
+}
+
+/// Calls `nullable` or `not_null` for the column of a field, which is the field
+/// name unless it has a `#[diesel(column_name = ...)]`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_nullability {
+    ([$($table:tt)*] $column:ident [$check:ident] []) => (
+        $check::<$($table)*::$column>();
+    );
+    ($table:tt $_field:ident $check:tt [[column_name $column:ident] $($opts:tt)*]) => (
+        $crate::__diesel_new_nullability! { $table $column $check [$($opts)*] }
+    );
+    ($table:tt $column:ident $_check:tt [[nullable] $($opts:tt)*]) => (
+        $crate::__diesel_new_nullability! { $table $column [nullable] [$($opts)*] }
+    );
+    ($table:tt $column:ident $check:tt [$_opt:tt $($opts:tt)*]) => (
+        $crate::__diesel_new_nullability! { $table $column $check [$($opts)*] }
+    );
+}
+
+/// Expands to `None` for fields marked with `#[new(redact)]`, and to a
+/// reference to the value of the field otherwise
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_redact {
+    ([] [$($value:tt)*]) => (
+        ::core::option::Option::Some(&$($value)*)
+    );
+    ([[redact] $($_opts:tt)*] $value:tt) => (
+        ::core::option::Option::None
+    );
+    ([$_opt:tt $($opts:tt)*] $value:tt) => (
+        $crate::__diesel_new_redact! { [$($opts)*] $value }
+    );
+}
+
+/// Looks up the value of a struct-level option and passes it, or the default,
+/// on to the callback macro
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_value {
+    (table_name [[table_name $($value:tt)*] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($value)*] }
+    );
+    (example_attr [[example_attr $($value:tt)*] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($value)*] }
+    );
+    (check_for_backend [[check_for_backend $($value:tt)*] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($value)*] }
+    );
+    (graphql [[graphql $($value:tt)*] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($value)*] }
+    );
+    (rename builder [[rename builder $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename update_from_new [[rename update_from_new $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename create [[rename create $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename create_with_retry [[rename create_with_retry $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename import [[rename import $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename diff [[rename diff $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename from_new [[rename from_new $name:ident] $($_opts:tt)*] $_default:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$name] }
+    );
+    (rename $method:ident [] [$($default:tt)*] [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($default)*] }
+    );
+    (rename $method:ident [$_opt:tt $($opts:tt)*] $default:tt $cb:tt $args:tt) => (
+        $crate::__diesel_new_value! { rename $method [$($opts)*] $default $cb $args }
+    );
+    ($key:ident [] [$($default:tt)*] [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($default)*] }
+    );
+    ($key:ident [$_opt:tt $($opts:tt)*] $default:tt $cb:tt $args:tt) => (
+        $crate::__diesel_new_value! { $key [$($opts)*] $default $cb $args }
+    );
+}
+
+/// Fails to compile for the options that can't be used on a struct with
+/// generics
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_generic_opt {
+    (builder) => {
+        $crate::__diesel_new_generic_opt! { @unsupported builder }
+    };
+    (check_for_backend) => {
+        $crate::__diesel_new_generic_opt! { @unsupported check_for_backend }
+    };
+    (conversions) => {
+        $crate::__diesel_new_generic_opt! { @unsupported conversions }
+    };
+    (cqrs) => {
+        $crate::__diesel_new_generic_opt! { @unsupported cqrs }
+    };
+    (create) => {
+        $crate::__diesel_new_generic_opt! { @unsupported create }
+    };
+    (default) => {
+        $crate::__diesel_new_generic_opt! { @unsupported default }
+    };
+    (diff) => {
+        $crate::__diesel_new_generic_opt! { @unsupported diff }
+    };
+    (display) => {
+        $crate::__diesel_new_generic_opt! { @unsupported display }
+    };
+    (dynamic) => {
+        $crate::__diesel_new_generic_opt! { @unsupported dynamic }
+    };
+    (event) => {
+        $crate::__diesel_new_generic_opt! { @unsupported event }
+    };
+    (from_form) => {
+        $crate::__diesel_new_generic_opt! { @unsupported from_form }
+    };
+    (from_json) => {
+        $crate::__diesel_new_generic_opt! { @unsupported from_json }
+    };
+    (graphql) => {
+        $crate::__diesel_new_generic_opt! { @unsupported graphql }
+    };
+    (proptest) => {
+        $crate::__diesel_new_generic_opt! { @unsupported proptest }
+    };
+    (raw_insert) => {
+        $crate::__diesel_new_generic_opt! { @unsupported raw_insert }
+    };
+    (retry) => {
+        $crate::__diesel_new_generic_opt! { @unsupported retry }
+    };
+    (update_from_new) => {
+        $crate::__diesel_new_generic_opt! { @unsupported update_from_new }
+    };
+    (update_struct) => {
+        $crate::__diesel_new_generic_opt! { @unsupported update_struct }
+    };
+    (verify_schema) => {
+        $crate::__diesel_new_generic_opt! { @unsupported verify_schema }
+    };
+    (with_id) => {
+        $crate::__diesel_new_generic_opt! { @unsupported with_id }
+    };
+    (@unsupported $opt:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "`#[new(",
+            ::core::stringify!($opt),
+            ")]` can't be used on a struct with generic parameters"
+        ));
+    };
+    ($_opt:ident) => {};
+}
+
+/// Checks that a method in `#[new(rename(...))]` can be renamed before passing
+/// the arguments on to the callback macro
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_renamable {
+    (builder [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    (update_from_new [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    (create [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    (create_with_retry [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    (import [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    (diff [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    (from_new [$($cb:tt)*] { $($args:tt)* }) => ($($cb)*! { $($args)* });
+    ($method:ident $cb:tt $args:tt) => (
+        ::core::compile_error!(::core::concat!(
+            "`", ::core::stringify!($method), "` can't be renamed, expected one of `builder`, ",
+            "`update_from_new`, `create`, `create_with_retry`, `import`, `diff` or `from_new`",
+        ));
+    );
+}
+
+#[cfg(feature = "dynamic-schema")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_dynamic {
+    ($StructName:ident $New:ident [$($table:tt)*]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[allow(dead_code)]
+                impl $New {
+                    /// Insert into a table that is only known by name at runtime, such
+                    /// as a partition, which has the same columns as the table this
+                    /// struct is `Insertable` for
+                    pub fn insert_into_dynamic<Name: ::std::borrow::Borrow<str>>(
+                        self,
+                        name: Name,
+                    ) -> $crate::dynamic::DynamicInsert<Name, <Self as diesel::Insertable<$($table)*::table>>::Values> {
+                        $crate::dynamic::insert_into::<$($table)*::table, _, _>(name, self)
+                    }
+                }
+            }
+        }
+    );
+}
+
+#[cfg(not(feature = "dynamic-schema"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_dynamic {
+    ($($_tt:tt)*) => {
+        ::core::compile_error!("`#[new(dynamic)]` requires the `dynamic-schema` feature");
+    };
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_event {
+    ($($args:tt)*) => {
+        $crate::diesel_new! { $($args)* }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_event {
+    ($($_tt:tt)*) => {
+        ::core::compile_error!("`#[new(event)]` requires the `serde` feature");
+    };
+}
+
+#[cfg(feature = "serde_json")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_json {
+    ($StructName:ident $New:ident [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident [$field_ty:ty]])*]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                // Deprecated fields are only moved, which shouldn't warn
+                #[allow(deprecated)]
+                impl ::core::convert::TryFrom<$crate::json::Value> for $New {
+                    type Error = $crate::json::FromJsonError;
+
+                    fn try_from(value: $crate::json::Value) -> ::core::result::Result<Self, Self::Error> {
+                        #[allow(unused_mut, unused_variables)]
+                        let mut object = $crate::json::object(value)?;
+
+                        ::core::result::Result::Ok(Self {
+                            $($field_name: $crate::__diesel_new_json!(
+                                @field $field_opts $field_ty, object, ::core::stringify!($field_name), ::core::stringify!($field_ty)
+                            )?,)*
+                        })
+                    }
+                }
+            }
+        }
+    );
+    (@field [[nullable] $($_opts:tt)*] $field_ty:ty, $object:ident, $field:expr, $expected:expr) => (
+        $crate::json::optional::<$field_ty>(&mut $object, $field, $expected)
+    );
+    (@field $_opts:tt $field_ty:ty, $object:ident, $field:expr, $expected:expr) => (
+        $crate::json::required::<$field_ty>(&mut $object, $field, $expected)
+    );
+}
+
+#[cfg(not(feature = "serde_json"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_json {
+    ($($_tt:tt)*) => {
+        ::core::compile_error!("`#[new(from_json)]` requires the `serde_json` feature");
+    };
+}
+
+#[cfg(feature = "form")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_form {
+    ($StructName:ident $New:ident [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident [$field_ty:ty]])*]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                // Deprecated fields are only moved, which shouldn't warn
+                #[allow(deprecated)]
+                impl<'form> ::core::convert::TryFrom<&'form ::std::collections::HashMap<::std::string::String, ::std::string::String>>
+                    for $New
+                {
+                    type Error = $crate::form::FromFormError;
+
+                    #[allow(unused_variables)]
+                    fn try_from(
+                        form: &'form ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+                    ) -> ::core::result::Result<Self, Self::Error> {
+                        let mut errors = ::std::vec::Vec::new();
+                        $(
+                            let $field_name = $crate::__diesel_new_form!(
+                                @field $field_opts form, ::core::stringify!($field_name), ::core::stringify!($field_ty), errors
+                            );
+                        )*
+
+                        // Every field is parsed before returning, so that all of the
+                        // errors are reported at once
+                        match ($($field_name,)*) {
+                            ($(::core::option::Option::Some($field_name),)*) if errors.is_empty() => {
+                                ::core::result::Result::Ok(Self { $($field_name,)* })
+                            }
+                            _ => ::core::result::Result::Err($crate::form::FromFormError { errors }),
+                        }
+                    }
+                }
+            }
+        }
+    );
+    (@field [[nullable] $($_opts:tt)*] $form:ident, $field:expr, $expected:expr, $errors:ident) => (
+        $crate::form::optional($form, $field, $expected, &mut $errors)
+    );
+    (@field $_opts:tt $form:ident, $field:expr, $expected:expr, $errors:ident) => (
+        $crate::form::required($form, $field, $expected, &mut $errors)
+    );
+}
+
+#[cfg(not(feature = "form"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_form {
+    ($($_tt:tt)*) => {
+        ::core::compile_error!("`#[new(from_form)]` requires the `form` feature");
+    };
+}
+
+#[cfg(feature = "async")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_graphql {
+    ([$struct_vis:vis] $StructName:ident $New:ident [$($table:tt)*] [$pool:ty]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                #[doc = "GraphQL mutations for [`" $StructName "`], to be merged into the mutation root with `#[derive(MergedObject)]`"]
+                #[derive(Debug, Default, Clone, Copy)]
+                $struct_vis struct [< $StructName Mutation >];
+
+                #[async_graphql::Object]
+                impl [< $StructName Mutation >] {
+                    #[doc = "Creates a new " $StructName]
+                    async fn [< create_ $StructName:snake >](
+                        &self,
+                        ctx: &async_graphql::Context<'_>,
+                        input: $New,
+                    ) -> async_graphql::Result<$StructName> {
+                        let mut conn = ctx.data::<$pool>()?.get().await?;
+                        let row: $StructName = $crate::InsertNewAsync::insert_returning_async(input, &mut *conn).await?;
+
+                        ::core::result::Result::Ok(row)
+                    }
+                }
+            }
+        }
+    );
+}
+
+#[cfg(not(feature = "async"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_graphql {
+    ($($_tt:tt)*) => {
+        ::core::compile_error!("`#[new(graphql(...))]` requires the `async` feature");
+    };
+}
+
+// Each alias for a field with `#[new(pg_ty(...) = ...)]` or `sqlite_ty` is only
+// defined when its own predicate holds and none of the ones before it do, and
+// the type of the struct is used when none of them hold
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_backend_ty {
+    ($Alias:ident $default:tt [$($seen:tt)*] [[($($cfg:tt)*) [$ty:ty]] $($backend:tt)*]) => {
+        #[cfg(all(not(any($($seen)*)), $($cfg)*))]
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        type $Alias = $ty;
+
+        $crate::__diesel_new_backend_ty! { $Alias $default [$($seen)* $($cfg)*,] [$($backend)*] }
+    };
+    ($Alias:ident [$default:ty] [$($seen:tt)*] []) => {
+        #[cfg(not(any($($seen)*)))]
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        type $Alias = $default;
+    };
+}
+
+#[cfg(feature = "wasm")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_wasm {
+    () => {};
+}
+
+#[cfg(not(feature = "wasm"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_wasm {
+    () => {
+        ::core::compile_error!("`#[new(wasm)]` requires the `wasm` feature");
+    };
+}
+
+#[cfg(feature = "test-support")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_proptest {
+    ($StructName:ident $New:ident [$($table:tt)*] [$([$field_opts:tt $field_meta:tt $field_vis:tt $field_name:ident [$field_ty:ty]])+]) => (
+        $crate::paste! {
+            [< __diesel_new_ $StructName _allowed >]! {
+                // The test is generated next to the struct, so that it can read the
+                // fields of the struct even when they are private
+                #[cfg(test)]
+                #[test]
+                #[allow(deprecated)]
+                fn [< $StructName:snake _round_trips_random_inserts_through_sqlite >]() {
+                    use $crate::test_support::proptest;
+
+                    proptest::proptest!(|($($field_name in proptest::arbitrary::any::<$field_ty>()),+)| {
+                        let mut conn = <diesel::sqlite::SqliteConnection as diesel::Connection>::establish(":memory:")
+                            .expect("failed to open an in-memory SQLite database");
+
+                        $crate::test_support::create_sqlite_table($($table)*::table, &mut conn)
+                            .expect("failed to create the table from its schema");
+
+                        let new = $New { $($field_name),+ };
+
+                        diesel::RunQueryDsl::execute(diesel::insert_into($($table)*::table).values(&new), &mut conn)
+                            .expect("failed to insert the New struct");
+
+                        let entity: $StructName = diesel::RunQueryDsl::first($($table)*::table, &mut conn)
+                            .expect("failed to read the entity back");
+
+                        $(
+                            proptest::prop_assert_eq!(
+                                &entity.$field_name, &new.$field_name,
+                                "`{}` did not round-trip", ::core::stringify!($field_name)
+                            );
+                        )+
+                    });
+                }
+            }
+        }
+    );
+}
+
+#[cfg(not(feature = "test-support"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_proptest {
+    ($($_tt:tt)*) => {
+        // Only the tests need the feature, which is usually only enabled for
+        // them through `dev-dependencies`
+        #[cfg(test)]
+        ::core::compile_error!("`#[new(proptest)]` requires the `test-support` feature");
+    };
+}
+
+/// The column of a field, which is the field's name unless it has a
+/// `#[diesel(column_name = ...)]`. `[column_name]` always comes first in the
+/// options of a field, other than `[nullable]`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_column {
+    ($table:ident $_field_name:ident [$([nullable])? [column_name $column:ident] $($_opts:tt)*]) => {
+        $table::$column
+    };
+    ($table:ident $field_name:ident $_opts:tt) => {
+        $table::$field_name
+    };
+}
+
+/// Looks up a struct-level `#[new(...)]` flag and passes either the `then` or
+/// the `else` tokens on to the callback macro
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __diesel_new_flag {
+    (manual_insertable [[manual_insertable] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (check_nullable [[check_nullable] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (update_from_new [[update_from_new] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (update_struct [[update_struct] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (cqrs [[cqrs] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (treat_none_as_null [[treat_none_as_null] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (conversions [[conversions] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (create [[create] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (retry [[retry] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (diff [[diff] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (raw_insert [[raw_insert] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (with_id [[with_id] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (wasm [[wasm] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (proptest [[proptest] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (verify_schema [[verify_schema] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (from_form [[from_form] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (dynamic [[dynamic] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (from_json [[from_json] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (builder [[builder] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (drop_deprecated [[drop_deprecated] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (check_id [[check_id] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (reordered [[reordered] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (display [[display] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    (event [[event] $($_opts:tt)*] [$($then:tt)*] $_else:tt [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($then)*] }
+    );
+    ($flag:ident [] $_then:tt [$($else:tt)*] [$($cb:tt)*] { $($args:tt)* }) => (
+        $($cb)*! { $($args)* [$($else)*] }
+    );
+    ($flag:ident [$_opt:tt $($opts:tt)*] $then:tt $else:tt $cb:tt $args:tt) => (
+        $crate::__diesel_new_flag! { $flag [$($opts)*] $then $else $cb $args }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::debug_query;
+    use diesel::prelude::*;
+
+    table! {
+        users(id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    super::diesel_new! {
+        #[derive(Debug, Clone, Queryable, AsChangeset)]
+        #[diesel(table_name = users)]
+        pub struct User {
+            id: i32,
+            pub name: String
+        }
+    }
+
+    use super::apply;
+
+    #[derive(Identifiable)]
+    #[apply(NewInsertable!)]
+    #[derive(Queryable, AsChangeset)]
+    #[diesel(table_name = users)]
+    pub struct SuperUser {
+        #[allow(dead_code)]
+        id: i32,
+        pub name: String,
+    }
+
+    #[apply(NewInsertable!)]
+    #[derive(Debug, diesel::Identifiable, Queryable)]
+    #[diesel(table_name = users)]
+    pub struct Moderator {
+        #[allow(dead_code)]
+        id: i32,
+        pub name: String,
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(derive(Clone, PartialEq), derive(Debug))]
+    #[derive(Queryable, Selectable, Identifiable, AsChangeset)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Reviewer {
+        id: i32,
+        pub name: String,
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(manual_insertable)]
+    #[derive(Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct ShoutyUser {
+        id: i32,
+        pub name: String,
+    }
+
+    impl Insertable<users::table> for NewShoutyUser {
+        type Values = <(diesel::dsl::Eq<users::name, String>,) as Insertable<users::table>>::Values;
+
+        fn values(self) -> Self::Values {
+            (users::name.eq(self.name.to_uppercase()),).values()
+        }
+    }
+
+    table! {
+        accounts(id) {
+            id -> Integer,
+            email -> Text,
+            name -> Nullable<Text>,
+            admin -> Bool,
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(group = "signup", group = "profile", check_nullable, display, builder)]
+    #[new(doc = "Fields for signing up a new " {Entity})]
+    #[derive(Queryable)]
+    #[diesel(table_name = accounts)]
+    #[allow(dead_code)]
+    pub struct Account {
+        id: i32,
+        #[new(group = "signup", redact)]
+        pub email: String,
+        #[new(group = "signup", group = "profile")]
+        pub name: Option<String>,
+        #[deprecated(note = "use roles instead")]
+        #[new(drop_deprecated)]
+        pub admin: bool,
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(update_struct, diff, verify_schema)]
+    #[derive(Clone, Queryable)]
+    #[diesel(table_name = accounts)]
+    #[allow(dead_code)]
+    pub struct Contact {
+        id: i32,
+        pub email: String,
+        #[new(treat_none_as_null)]
+        pub name: Option<String>,
+        pub admin: bool,
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(update_struct, update_from_new)]
+    #[derive(Queryable, AsChangeset)]
+    #[diesel(table_name = accounts)]
+    #[allow(dead_code)]
+    pub struct Listing {
+        id: i32,
+        #[new(immutable)]
+        pub email: String,
+        pub name: Option<String>,
+        pub admin: bool,
+    }
+
+    #[test]
+    fn it_never_updates_immutable_fields() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT NOT NULL, name TEXT, admin BOOLEAN NOT NULL)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        NewListing {
+            email: String::from("ferris@example.com"),
+            name: None,
+            admin: false,
+        }
+        .insert_into(accounts::table)
+        .execute(&mut conn)
+        .unwrap();
+
+        let listing = Listing::update_from_new(
+            1,
+            NewListing {
+                email: String::from("corro@example.com"),
+                name: Some(String::from("Ferris")),
+                admin: false,
+            },
+            &mut conn,
+        )
+        .unwrap();
+
+        assert_eq!(listing.email, "ferris@example.com");
+        assert_eq!(listing.name.as_deref(), Some("Ferris"));
+
+        // The changeset has no `email` to set
+        let update = UpdateListing {
+            name: None,
+            admin: Some(true),
+        };
+        let query = diesel::update(accounts::table.find(1)).set(update);
+
+        assert_eq!(
+            "UPDATE `accounts` SET `admin` = ? WHERE (`accounts`.`id` = ?) -- binds: [true, 1]",
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[deny(non_snake_case)]
+    mod legacy {
+        use super::accounts;
+        use crate::apply;
+        use diesel::prelude::*;
+
+        // Field names from a legacy schema, which also end up in the builder
+        #[apply(NewInsertable!)]
+        #[new(allow(non_snake_case), builder)]
+        #[derive(Queryable)]
+        #[diesel(table_name = accounts)]
+        #[allow(dead_code, non_snake_case)]
+        pub struct LegacyAccount {
+            id: i32,
+            #[diesel(column_name = email)]
+            pub eMail: String,
+            pub name: Option<String>,
+            #[diesel(column_name = admin)]
+            pub isAdmin: bool,
+        }
+    }
+
+    #[test]
+    fn it_allows_lints_on_generated_items() {
+        let account = legacy::NewLegacyAccount::builder()
+            .eMail("ferris@example.com")
+            .isAdmin(true)
+            .build();
+
+        assert_eq!(account.eMail, "ferris@example.com");
+        assert!(account.isAdmin);
+    }
+
+    #[test]
+    fn it_reads_the_row_instead_of_applying_an_empty_changeset() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT NOT NULL, name TEXT, admin BOOLEAN NOT NULL)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        NewListing {
+            email: String::from("ferris@example.com"),
+            name: None,
+            admin: false,
+        }
+        .insert_into(accounts::table)
+        .execute(&mut conn)
+        .unwrap();
+
+        assert!(UpdateListing::default().is_empty());
+        assert!(diesel::update(accounts::table.find(1))
+            .set(UpdateListing::default())
+            .execute(&mut conn)
+            .is_err());
+
+        let listing = UpdateListing::default().update(1, &mut conn).unwrap();
+        assert!(!listing.admin);
+
+        let update = UpdateListing {
+            name: None,
+            admin: Some(true),
+        };
+        assert!(!update.is_empty());
+        assert!(update.update(1, &mut conn).unwrap().admin);
+
+        // `None` sets the name of a contact to `NULL`, so it is always written
+        assert!(!UpdateContact::default().is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn it_verifies_the_schema_of_the_database() {
+        use super::schema::{ColumnMismatch, SchemaMismatch};
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+
+        assert!(matches!(
+            NewContact::verify_schema(&mut conn),
+            Err(SchemaMismatch::MissingTable { table }) if table == "accounts"
+        ));
+
+        diesel::sql_query("CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT, name TEXT)")
+            .execute(&mut conn)
+            .unwrap();
+
+        match NewContact::verify_schema(&mut conn) {
+            Err(SchemaMismatch::Columns { table, columns }) => {
+                assert_eq!(table, "accounts");
+                assert_eq!(
+                    columns,
+                    vec![
+                        ColumnMismatch::Nullability {
+                            column: "email",
+                            nullable: false
+                        },
+                        ColumnMismatch::Missing { column: "admin" },
+                    ]
+                );
+            }
+            result => panic!("expected mismatched columns, got {:?}", result),
+        }
+
+        diesel::sql_query("DROP TABLE accounts")
+            .execute(&mut conn)
+            .unwrap();
+        diesel::sql_query(
+            "CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT NOT NULL, name TEXT, admin BOOLEAN NOT NULL)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        assert!(NewContact::verify_schema(&mut conn).is_ok());
+    }
+
+    #[test]
+    fn it_sets_null_through_an_update_struct() {
+        let contact = Contact {
+            id: 1,
+            email: String::from("ferris@example.com"),
+            name: Some(String::from("Ferris")),
+            admin: false,
+        };
+        let update = UpdateContact::diff(
+            &contact,
+            &Contact {
+                name: None,
+                ..contact.clone()
+            },
+        );
+
+        assert_eq!(update.email, None);
+
+        let query = diesel::update(accounts::table.find(contact.id)).set(update);
+
+        assert_eq!(
+            "UPDATE `accounts` SET `name` = ? WHERE (`accounts`.`id` = ?) -- binds: [None, 1]",
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    table! {
+        memberships(user_id, account_id) {
+            user_id -> Integer,
+            account_id -> Integer,
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(no_id)]
+    #[derive(Debug, PartialEq, Queryable)]
+    #[diesel(table_name = memberships)]
+    pub struct Membership {
+        pub user_id: i32,
+        pub account_id: i32,
+    }
+
+    #[test]
+    fn it_passes_every_field_through_without_an_id() {
+        let query = NewMembership {
+            user_id: 1,
+            account_id: 2,
+        }
+        .insert_into(memberships::table);
+
+        assert_eq!(
+            "INSERT INTO `memberships` (`user_id`, `account_id`) VALUES (?, ?) -- binds: [1, 2]",
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let _ = Membership {
+            user_id: 1,
+            account_id: 2,
+        };
+    }
+
+    table! {
+        tickets(ticket_id) {
+            title -> Text,
+            ticket_id -> Integer,
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(update_from_new, check_id, conversions)]
+    #[derive(Debug, PartialEq, Queryable, AsChangeset)]
+    #[diesel(table_name = tickets, primary_key(ticket_id))]
+    pub struct Ticket {
+        pub title: String,
+        #[new(primary_key)]
+        pub ticket_id: i32,
+    }
+
+    #[test]
+    fn it_strips_a_primary_key_anywhere_in_the_struct() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE tickets (title TEXT NOT NULL, ticket_id INTEGER PRIMARY KEY)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        assert_eq!(NewTicket::SKIPPED_COLUMNS, ["ticket_id"]);
+
+        NewTicket {
+            title: String::from("Crab on the loose"),
+        }
+        .insert_into(tickets::table)
+        .execute(&mut conn)
+        .unwrap();
+
+        let ticket = Ticket::update_from_new(
+            1,
+            NewTicket {
+                title: String::from("Crab caught"),
+            },
+            &mut conn,
+        )
+        .unwrap();
+
+        assert_eq!(
+            ticket,
+            Ticket {
+                title: String::from("Crab caught"),
+                ticket_id: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn it_converts_between_the_struct_and_its_new_struct() {
+        let ticket = Ticket {
+            title: String::from("Crab spotted"),
+            ticket_id: 1,
+        };
+
+        let new_ticket = NewTicket::from(ticket);
+        assert_eq!(new_ticket.title, "Crab spotted");
+
+        assert_eq!(
+            Ticket::from_new(2, new_ticket),
+            Ticket {
+                title: String::from("Crab spotted"),
+                ticket_id: 2,
+            }
+        );
+    }
+
+    table! {
+        notes(id) {
+            id -> Integer,
+            created_at -> Text,
+            body -> Text,
+            updated_at -> Text,
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = notes)]
+    #[allow(dead_code)]
+    pub struct Note {
+        pub id: i32,
+        #[new(skip)]
+        pub created_at: String,
+        pub body: String,
+        #[new(skip)]
+        #[diesel(column_name = updated_at)]
+        pub modified_at: String,
+    }
+
+    #[test]
+    fn it_lists_the_skipped_columns() {
+        assert_eq!(NewUser::SKIPPED_COLUMNS, ["id"]);
+        assert!(NewMembership::SKIPPED_COLUMNS.is_empty());
+        assert_eq!(NewNote::SKIPPED_COLUMNS, ["id", "created_at", "updated_at"]);
+    }
+
+    #[test]
+    fn it_leaves_skipped_fields_to_the_database() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, created_at TEXT NOT NULL DEFAULT 'then', body TEXT NOT NULL, updated_at TEXT NOT NULL DEFAULT 'now')",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        let new_note = NewNote {
+            body: String::from("Hello"),
+        };
+        let query = new_note.insert_into(notes::table);
+        assert_eq!(
+            r#"INSERT INTO `notes` (`body`) VALUES (?) -- binds: ["Hello"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        query.execute(&mut conn).unwrap();
+        let note: Note = notes::table.first(&mut conn).unwrap();
+
+        assert_eq!(note.created_at, "then");
+        assert_eq!(note.body, "Hello");
+        assert_eq!(note.modified_at, "now");
+    }
+
+    table! {
+        chores(id) {
+            id -> Integer,
+            title -> Text,
+            status -> Text,
+            attempts -> Integer,
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(group = "quick", conversions)]
+    #[derive(Debug, Clone, PartialEq, Queryable)]
+    #[diesel(table_name = chores)]
+    pub struct Chore {
+        pub id: i32,
+        #[new(group = "quick")]
+        pub title: String,
+        #[new(default = String::from("open"))]
+        pub status: String,
+        #[new(default)]
+        #[diesel(column_name = attempts)]
+        pub tries: i32,
+    }
+
+    #[test]
+    fn it_inserts_defaults_for_fields_left_out() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE chores (id INTEGER PRIMARY KEY, title TEXT NOT NULL, status TEXT NOT NULL, attempts INTEGER NOT NULL)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        let new_chore = NewChore {
+            title: String::from("Sweep"),
+        };
+        let query = new_chore.clone().insert_into(chores::table);
+        assert_eq!(
+            r#"INSERT INTO `chores` (`title`, `status`, `attempts`) VALUES (?, ?, ?) -- binds: ["Sweep", "open", 0]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+        query.execute(&mut conn).unwrap();
+
+        diesel::insert_into(chores::table)
+            .values(vec![NewChoreQuick {
+                title: String::from("Mop"),
+            }])
+            .execute(&mut conn)
+            .unwrap();
+
+        let chores: Vec<Chore> = chores::table.order(chores::id).load(&mut conn).unwrap();
+        assert_eq!(chores[0], Chore::from_new(1, new_chore));
+        assert_eq!(chores[1].title, "Mop");
+        assert_eq!(chores[1].status, "open");
+        assert_eq!(chores[1].tries, 0);
+    }
+
+    table! {
+        invites(id) {
+            id -> Integer,
+            email -> Text,
+            note -> Nullable<Text>,
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(name = CreateInvite, group = "bulk", builder)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = invites)]
+    #[allow(dead_code)]
+    pub struct Invite {
+        id: i32,
+        #[new(group = "bulk")]
+        pub email: String,
+        pub note: Option<String>,
+    }
+
+    #[test]
+    fn it_names_the_new_struct() {
+        let create_invite = CreateInvite::builder()
+            .email("ferris@example.com")
+            .note(Some(String::from("Welcome")))
+            .build();
+        let query = create_invite.insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`, `note`) VALUES (?, ?) -- binds: ["ferris@example.com", "Welcome"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let query = CreateInviteBulk {
+            email: String::from("ferris@example.com"),
+        }
+        .insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`) VALUES (?) -- binds: ["ferris@example.com"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+        assert_eq!(CreateInvite::SKIPPED_COLUMNS, ["id"]);
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(group = "bulk")]
+    #[derive(Debug, Selectable)]
+    #[diesel(table_name = invites)]
+    #[allow(dead_code)]
+    pub struct BorrowedInvite<'a, N: AsRef<str> + Clone> {
+        id: i32,
+        #[new(group = "bulk")]
+        pub email: &'a str,
+        #[new(group = "bulk")]
+        pub note: Option<N>,
+    }
+
+    #[test]
+    fn it_carries_generic_parameters_onto_the_new_struct() {
+        let email = String::from("ferris@example.com");
+        let query = NewBorrowedInvite {
+            email: &email,
+            note: Some("Welcome"),
+        }
+        .insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`, `note`) VALUES (?, ?) -- binds: ["ferris@example.com", "Welcome"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let query = NewBorrowedInviteBulk::<String> {
+            email: &email,
+            note: None,
+        }
+        .insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`) VALUES (?) -- binds: ["ferris@example.com"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+        assert_eq!(NewBorrowedInvite::<&str>::SKIPPED_COLUMNS, ["id"]);
+    }
+
+    super::diesel_new_all! {
+        #[allow(dead_code)]
+        mod catalog {
+            use super::invites;
+            use diesel::prelude::*;
+
+            #[derive(Debug, Queryable)]
+            #[diesel(table_name = invites)]
+            pub struct CatalogInvite {
+                pub id: i32,
+                pub email: String,
+                pub note: Option<String>,
+            }
+
+            #[derive(Debug, Selectable)]
+            #[diesel(table_name = invites)]
+            pub struct CatalogDraft<'a> {
+                pub id: i32,
+                pub email: &'a str,
+            }
+
+            #[new(ignore)]
+            pub struct Summary {
+                pub invites: usize,
+            }
+
+            #[derive(Debug)]
+            #[new(builder, ignore)]
+            #[new(name = CreateReport)]
+            pub struct Report {
+                pub invites: usize,
+            }
+
+            pub struct Count(pub usize);
+
+            impl Summary {
+                pub fn count(&self) -> Count {
+                    Count(self.invites)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_generates_the_new_structs_of_a_module() {
+        let query = catalog::NewCatalogInvite {
+            email: String::from("ferris@example.com"),
+            note: None,
+        }
+        .insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`) VALUES (?) -- binds: ["ferris@example.com"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let query = catalog::NewCatalogDraft {
+            email: "ferris@example.com",
+        }
+        .insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`) VALUES (?) -- binds: ["ferris@example.com"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        assert_eq!(catalog::Summary { invites: 2 }.count().0, 2);
+        assert_eq!(catalog::Report { invites: 2 }.invites, 2);
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(cfg(test), name = RegisterGuest)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Guest {
+        id: i32,
+        pub name: String,
+    }
+
+    // The `#[doc(alias = "RegisterGuest")]` on `Guest` only shows up in rustdoc,
+    // so this checks that the name it is taken from is the one in the options
+    #[test]
+    fn it_names_the_new_struct_after_a_cfg() {
+        use super::HasNewInsertable;
+        use std::any::TypeId;
+
+        let query = RegisterGuest {
+            name: String::from("Ferris"),
+        }
+        .insert_into(users::table);
+        assert_eq!(
+            r#"INSERT INTO `users` (`name`) VALUES (?) -- binds: ["Ferris"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+        assert_eq!(
+            TypeId::of::<<Guest as HasNewInsertable>::New>(),
+            TypeId::of::<RegisterGuest>()
+        );
+    }
+
+    #[test]
+    fn it_links_each_struct_to_its_new_struct() {
+        use super::HasNewInsertable;
+        use std::any::TypeId;
+
+        assert_eq!(
+            TypeId::of::<<SuperUser as HasNewInsertable>::New>(),
+            TypeId::of::<NewSuperUser>()
+        );
+        assert_eq!(
+            TypeId::of::<<Invite as HasNewInsertable>::New>(),
+            TypeId::of::<CreateInvite>()
+        );
+        assert_eq!(
+            TypeId::of::<<BorrowedInvite<'static, String> as HasNewInsertable>::New>(),
+            TypeId::of::<NewBorrowedInvite<'static, String>>()
+        );
+    }
+
+    #[cfg(feature = "proc-macro")]
+    #[crate::new_insertable(check_nullable)]
+    #[derive(Debug, Selectable)]
+    #[diesel(table_name = invites)]
+    #[allow(dead_code)]
+    pub struct InviteDraft<'a, N>
+    where
+        N: AsRef<str> + Clone,
+    {
+        id: i32,
+        pub email: &'a str,
+        pub note: Option<N>,
+    }
+
+    #[cfg(feature = "proc-macro")]
+    #[test]
+    fn it_generates_a_new_struct_with_the_attribute_macro() {
+        let query = NewInviteDraft {
+            email: "ferris@example.com",
+            note: Some("Welcome"),
+        }
+        .insert_into(invites::table);
+        assert_eq!(
+            r#"INSERT INTO `invites` (`email`, `note`) VALUES (?, ?) -- binds: ["ferris@example.com", "Welcome"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    /// The patterns of the arms of `macro_rules! $name` in this file
+    #[cfg(feature = "proc-macro")]
+    fn arms_of(name: &str) -> Vec<Vec<proc_macro2::TokenTree>> {
+        use proc_macro2::{TokenStream, TokenTree};
+
+        let source: TokenStream = include_str!("lib.rs").parse().unwrap();
+        let tokens = source.into_iter().collect::<Vec<_>>();
+        let body = tokens
+            .windows(4)
+            .find_map(|window| match window {
+                [TokenTree::Ident(keyword), TokenTree::Punct(_), TokenTree::Ident(ident), TokenTree::Group(body)]
+                    if keyword == "macro_rules" && ident == name =>
+                {
+                    Some(body.stream().into_iter().collect::<Vec<_>>())
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        body.windows(2)
+            .filter_map(|window| match window {
+                [TokenTree::Group(pattern), TokenTree::Punct(arrow)] if arrow.as_char() == '=' => {
+                    Some(pattern.stream().into_iter().collect())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The option at the start of the brackets after `$meta:tt` in the arms
+    /// of `diesel_new!` that start with `@head`, such as `[builder $(, ...)?]`
+    #[cfg(feature = "proc-macro")]
+    fn options_of_arms(head: &str, meta: &str) -> Vec<String> {
+        use proc_macro2::TokenTree;
+
+        let mut options = arms_of("diesel_new")
+            .into_iter()
+            .filter(|arm| matches!(arm.get(1), Some(TokenTree::Ident(ident)) if ident == head))
+            .filter_map(|arm| {
+                let at = arm.windows(4).position(|window| {
+                    matches!(window, [TokenTree::Punct(_), TokenTree::Ident(ident), TokenTree::Punct(_), TokenTree::Ident(tt)] if ident == meta && tt == "tt")
+                })?;
+                match arm.get(at + 4)? {
+                    TokenTree::Group(options) => match options.stream().into_iter().next()? {
+                        TokenTree::Ident(option) => Some(option.to_string()),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        options.sort();
+        options.dedup();
+        options
+    }
+
+    #[cfg(feature = "proc-macro")]
+    #[test]
+    fn it_knows_the_same_options_in_the_attribute_macro() {
+        use proc_macro2::TokenTree;
+
+        let (struct_options, field_options, unsupported_on_generics) =
+            diesel_autoincrement_new_struct_derive::__known_options!();
+
+        assert_eq!(struct_options, options_of_arms("opts", "meta"));
+        assert_eq!(field_options, options_of_arms("field_opts", "field_meta"));
+
+        let mut unsupported = arms_of("__diesel_new_generic_opt")
+            .into_iter()
+            .filter_map(|arm| match arm.first()? {
+                TokenTree::Ident(option) => Some(option.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        unsupported.sort();
+        assert_eq!(unsupported_on_generics, unsupported);
+    }
+
+    #[test]
+    fn it_generates_a_new_struct_per_group() {
+        let query = NewAccountSignup {
+            email: String::from("ferris@example.com"),
+            name: None,
+        }
+        .insert_into(accounts::table);
+
+        assert_eq!(
+            r#"INSERT INTO `accounts` (`email`) VALUES (?) -- binds: ["ferris@example.com"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let query = NewAccountProfile {
+            name: Some(String::from("Ferris")),
+        }
+        .insert_into(accounts::table);
+
+        assert_eq!(
+            r#"INSERT INTO `accounts` (`name`) VALUES (?) -- binds: ["Ferris"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let _ = NewAccount {
+            email: String::from("ferris@example.com"),
+            name: None,
+            admin: true,
+        };
+    }
+
+    #[cfg(feature = "dynamic-schema")]
+    #[apply(NewInsertable!)]
+    #[new(dynamic)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Pageview {
+        id: i32,
+        pub name: String,
+    }
+
+    #[cfg(feature = "dynamic-schema")]
+    #[test]
+    fn it_inserts_into_a_runtime_named_table() {
+        let query = NewPageview {
+            name: String::from("Ferris"),
+        }
+        .insert_into_dynamic("users_2024_06");
+
+        assert_eq!(
+            r#"INSERT INTO `users_2024_06` (`name`) VALUES (?) -- binds: ["Ferris"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[apply(NewInsertable!)]
+    #[new(from_json)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = accounts)]
+    #[allow(dead_code)]
+    pub struct Signup {
+        id: i32,
+        pub email: String,
+        pub name: Option<String>,
+        pub admin: bool,
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn it_converts_json_into_a_new_struct() {
+        let signup = NewSignup::try_from(serde_json::json!({
+            "email": "ferris@example.com",
+            "admin": false,
+        }))
+        .unwrap();
+
+        assert_eq!(signup.email, "ferris@example.com");
+        assert_eq!(signup.name, None);
+
+        let error = NewSignup::try_from(serde_json::json!({
+            "email": "ferris@example.com",
+            "admin": "no",
+        }))
+        .unwrap_err();
+
+        assert_eq!(
+            r#"field `admin` is not a valid `bool`: invalid type: string "no", expected a boolean"#,
+            error.to_string()
+        );
+
+        let error = NewSignup::try_from(serde_json::json!({
+            "email": "ferris@example.com",
+        }))
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            super::json::FromJsonError::Missing { field: "admin" }
+        ));
+    }
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(sqlite_type(name = "Text"))]
+    pub struct Citext;
+
+    table! {
+        use diesel::sql_types::*;
+        use super::Citext;
+
+        profiles(id) {
+            id -> Integer,
+            handle -> Citext,
+            display_name -> Text,
+        }
+    }
+
+    #[derive(Debug, PartialEq, diesel::AsExpression, diesel::FromSqlRow)]
+    #[diesel(sql_type = Citext)]
+    pub struct Handle(String);
+
+    impl diesel::serialize::ToSql<Citext, diesel::sqlite::Sqlite> for Handle {
+        fn to_sql<'b>(
+            &'b self,
+            out: &mut diesel::serialize::Output<'b, '_, diesel::sqlite::Sqlite>,
+        ) -> diesel::serialize::Result {
+            out.set_value(self.0.to_lowercase());
+            Ok(diesel::serialize::IsNull::No)
+        }
+    }
+
+    impl diesel::deserialize::FromSql<Citext, diesel::sqlite::Sqlite> for Handle {
+        fn from_sql(
+            value: diesel::sqlite::SqliteValue<'_, '_, '_>,
+        ) -> diesel::deserialize::Result<Self> {
+            <String as diesel::deserialize::FromSql<
+                diesel::sql_types::Text,
+                diesel::sqlite::Sqlite,
+            >>::from_sql(value)
+            .map(Handle)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Shouty(String);
+
+    impl From<String> for Shouty {
+        fn from(name: String) -> Self {
+            Shouty(name.to_uppercase())
+        }
+    }
+
+    impl From<Shouty> for String {
+        fn from(name: Shouty) -> Self {
+            name.0
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = profiles)]
+    #[allow(dead_code)]
+    pub struct Profile {
+        id: i32,
+        #[diesel(sql_type = Citext)]
+        pub handle: Handle,
+        #[diesel(deserialize_as = String)]
+        #[new(diesel(serialize_as = String))]
+        pub display_name: Shouty,
+    }
+
+    /// Can only be created from a string with an `@` in it
+    #[derive(Debug)]
+    pub struct EmailAddress(String);
+
+    impl TryFrom<&str> for EmailAddress {
+        type Error = &'static str;
+
+        fn try_from(email: &str) -> Result<Self, Self::Error> {
+            if email.contains('@') {
+                Ok(EmailAddress(email.to_owned()))
+            } else {
+                Err("not an email address")
+            }
+        }
+    }
+
+    impl From<EmailAddress> for String {
+        fn from(email: EmailAddress) -> Self {
+            email.0
+        }
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(update_struct)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = accounts)]
+    #[allow(dead_code)]
+    pub struct Patron {
+        id: i32,
+        #[new(type = EmailAddress, natural_key)]
+        pub email: String,
+        pub name: Option<String>,
+        pub admin: bool,
+    }
+
+    #[test]
+    fn it_inserts_a_validated_newtype_in_place_of_the_field() {
+        assert!(EmailAddress::try_from("ferris").is_err());
+
+        let query = NewPatron {
+            email: EmailAddress::try_from("ferris@example.com").unwrap(),
+            name: None,
+            admin: false,
+        }
+        .insert_into(accounts::table);
+
+        assert_eq!(
+            r#"INSERT INTO `accounts` (`email`, `admin`) VALUES (?, ?) -- binds: ["ferris@example.com", false]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[test]
+    fn it_updates_a_newtype_field_as_the_type_of_the_struct() {
+        let update = UpdatePatron {
+            email: Some(String::from("ferris@example.com")),
+            ..Default::default()
+        };
+        let query = diesel::update(accounts::table.find(1)).set(update);
+
+        assert_eq!(
+            r#"UPDATE `accounts` SET `email` = ? WHERE (`accounts`.`id` = ?) -- binds: ["ferris@example.com", 1]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+
+        let update = UpdatePatron::from(Patron {
+            id: 1,
+            email: String::from("ferris@example.com"),
+            name: None,
+            admin: true,
+        });
+        assert_eq!(update.email.as_deref(), Some("ferris@example.com"));
+    }
+
+    #[test]
+    fn it_extracts_the_natural_key() {
+        let new_patron = NewPatron {
+            email: EmailAddress::try_from("ferris@example.com").unwrap(),
+            name: None,
+            admin: false,
+        };
+        let (email,) = new_patron.natural_key();
+        assert_eq!(email.0, "ferris@example.com");
+
+        let patron = Patron {
+            id: 1,
+            email: String::from("ferris@example.com"),
+            name: None,
+            admin: true,
+        };
+        assert_eq!(patron.natural_key(), (&String::from("ferris@example.com"),));
+
+        let query = new_patron
+            .insert_into(accounts::table)
+            .on_conflict(NewPatron::natural_key_columns())
+            .do_nothing();
+
+        assert_eq!(
+            r#"INSERT INTO `accounts` (`email`, `admin`) VALUES (?, ?) ON CONFLICT (`email`) DO NOTHING -- binds: ["ferris@example.com", false]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[apply(NewInsertable!)]
+    #[derive(Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct SharedUser {
+        id: i32,
+        #[new(pg_ty(feature = "postgres") = &'static str, sqlite_ty(feature = "sqlite") = std::borrow::Cow<'static, str>)]
+        pub name: String,
+    }
+
+    #[test]
+    fn it_swaps_the_type_of_a_field_for_the_backend() {
+        // With `postgres`, the field is the `&str` that is converted into
+        #[allow(clippy::useless_conversion)]
+        let new_user = NewSharedUser {
+            name: "Ferris".into(),
+        };
+
+        let expected = if cfg!(feature = "postgres") {
+            std::any::type_name::<&str>()
+        } else if cfg!(feature = "sqlite") {
+            std::any::type_name::<std::borrow::Cow<str>>()
+        } else {
+            std::any::type_name::<String>()
+        };
+        assert_eq!(std::any::type_name_of_val(&new_user.name), expected);
+
+        let query = new_user.insert_into(users::table);
+        assert_eq!(
+            r#"INSERT INTO `users` (`name`) VALUES (?) -- binds: ["Ferris"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[test]
+    fn it_inserts_custom_sql_types() {
+        use super::InsertNew;
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE profiles (id INTEGER PRIMARY KEY, handle TEXT NOT NULL, display_name TEXT NOT NULL)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        let profile: Profile = NewProfile {
+            handle: Handle(String::from("Ferris")),
+            display_name: Shouty(String::from("Ferris")),
+        }
+        .insert_returning(&mut conn)
+        .unwrap();
+
+        assert_eq!(profile.handle, Handle(String::from("ferris")));
+        assert_eq!(profile.display_name, Shouty(String::from("FERRIS")));
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(
+        update_struct,
+        diff,
+        cqrs,
+        create,
+        retry,
+        raw_insert,
+        with_id,
+        check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg)
+    )]
+    #[derive(Queryable, serde::Serialize)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Member {
+        id: i32,
+        /// Shows up on their profile
+        #[diesel(column_name = name)]
+        #[serde(rename = "display_name")]
+        pub name: String,
+    }
+
+    #[test]
+    fn it_inserts_from_an_iterator_in_batches() {
+        use super::InsertNew;
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let rows = (0..5).map(|i| NewMember {
+            name: format!("Ferris {}", i),
+        });
+        let inserted = NewMember::insert_all(rows, 2, &mut conn).unwrap();
+
+        assert_eq!(inserted, 5);
+        assert_eq!(users::table.count().get_result::<i64>(&mut conn), Ok(5));
+    }
+
+    #[test]
+    fn it_rolls_back_a_transactional_batch_that_isnt_finished() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let mut inserter = super::BatchInserter::transactional(&mut conn, 2).unwrap();
+        for i in 0..3 {
+            inserter
+                .push(NewMember {
+                    name: format!("Ferris {}", i),
+                })
+                .unwrap();
+        }
+
+        assert_eq!(inserter.inserted(), 2);
+        drop(inserter);
+
+        assert_eq!(users::table.count().get_result::<i64>(&mut conn), Ok(0));
+
+        let mut inserter = super::BatchInserter::new(&mut conn, 2);
+        for i in 0..3 {
+            inserter
+                .push(NewMember {
+                    name: format!("Ferris {}", i),
+                })
+                .unwrap();
+        }
+        drop(inserter);
+
+        assert_eq!(users::table.count().get_result::<i64>(&mut conn), Ok(3));
+    }
+
+    #[test]
+    fn it_builds_a_new_struct_in_stages() {
+        let account = NewAccount::builder()
+            .email("ferris@example.com")
+            .admin(false)
+            .name(String::from("Ferris"))
+            .build();
+
+        assert_eq!(account.email, "ferris@example.com");
+        assert_eq!(account.name.as_deref(), Some("Ferris"));
+
+        let account = NewAccount::builder()
+            .email("ferris@example.com")
+            .admin(true)
+            .build();
+        assert_eq!(account.name, None);
+    }
+
+    #[test]
+    fn it_displays_a_new_struct_with_redacted_fields() {
+        let account = NewAccount {
+            email: String::from("ferris@example.com"),
+            name: Some(String::from("Ferris")),
+            admin: false,
+        };
+
+        assert_eq!(
+            r#"accounts(email=***, name=Some("Ferris"), admin=false)"#,
+            account.to_string()
+        );
+    }
+
+    #[test]
+    fn it_generates_an_update_struct_from_a_row() {
+        let member = Member {
+            id: 1,
+            name: String::from("Ferris"),
+        };
+
+        let query = diesel::update(users::table.find(member.id)).set(UpdateMember::from(member));
+
+        assert_eq!(
+            r#"UPDATE `users` SET `name` = ? WHERE (`users`.`id` = ?) -- binds: ["Ferris", 1]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[test]
+    fn it_diffs_two_snapshots_of_a_row() {
+        let before = Member {
+            id: 1,
+            name: String::from("Ferris"),
+        };
+        let after = Member {
+            id: 1,
+            name: String::from("Corro"),
+        };
+
+        assert_eq!(None, UpdateMember::diff(&before, &before).name);
+        assert_eq!(
+            Some(String::from("Corro")),
+            UpdateMember::diff(&before, &after).name
+        );
+    }
+
+    #[test]
+    fn it_generates_read_and_write_models() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        diesel::insert_into(users::table)
+            .values(MemberWriteModel {
+                name: String::from("Ferris"),
+            })
+            .execute(&mut conn)
+            .unwrap();
+
+        let member = users::table
+            .select(MemberReadModel::as_select())
+            .first(&mut conn)
+            .unwrap();
+
+        assert_eq!((member.id, member.name), (1, String::from("Ferris")));
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(cfg(not(test)))]
+    #[derive(Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Ghost {
+        id: i32,
+        name: String,
+    }
+
+    // This would conflict with the generated struct if it wasn't gated
+    #[allow(dead_code)]
+    struct NewGhost;
+
+    super::diesel_new! {
+        #[cfg(not(test))]
+        #[derive(Queryable)]
+        #[diesel(table_name = users)]
+        pub struct Phantom {
+            id: i32,
+            name: String,
+        }
+    }
+
+    // Just as with `#[new(cfg(...))]`, the struct's own `#[cfg(...)]` gates
+    // everything generated from it
+    #[allow(dead_code)]
+    struct NewPhantom;
+
+    #[test]
+    fn it_creates_and_returns_the_row() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let member = NewMember {
+            name: String::from("Ferris"),
+        }
+        .create(&mut conn)
+        .unwrap();
+
+        assert_eq!((member.id, member.name), (1, String::from("Ferris")));
+    }
+
+    #[test]
+    fn it_creates_with_retry() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let member = NewMember {
+            name: String::from("Ferris"),
+        }
+        .create_with_retry(&mut conn, &super::RetryPolicy::default())
+        .unwrap();
+
+        assert_eq!((member.id, member.name), (1, String::from("Ferris")));
+
+        let mut attempts = 0;
+        let policy = super::RetryPolicy::new(3).backoff(std::time::Duration::ZERO);
+        let result: diesel::QueryResult<()> = super::with_retry(&mut conn, &policy, |_| {
+            attempts += 1;
+            Err(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::SerializationFailure,
+                Box::new(String::from("could not serialize access")),
+            ))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let policy = policy.classify(|error| matches!(error, diesel::result::Error::NotFound));
+        let result: diesel::QueryResult<()> = super::with_retry(&mut conn, &policy, |_| {
+            attempts += 1;
+            match attempts {
+                1 => Err(diesel::result::Error::NotFound),
+                _ => Err(diesel::result::Error::DatabaseError(
+                    diesel::result::DatabaseErrorKind::SerializationFailure,
+                    Box::new(String::from("could not serialize access")),
+                )),
+            }
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn it_retries_serialization_failures_and_deadlocks_only() {
+        use diesel::result::{DatabaseErrorKind, Error};
+
+        let error = |kind, message: &str| Error::DatabaseError(kind, Box::new(message.to_owned()));
+
+        assert!(super::is_retryable(&error(
+            DatabaseErrorKind::SerializationFailure,
+            "Deadlock found when trying to get lock; try restarting transaction",
+        )));
+        assert!(super::is_retryable(&error(
+            DatabaseErrorKind::Unknown,
+            "deadlock detected",
+        )));
+
+        assert!(!super::is_retryable(&error(
+            DatabaseErrorKind::Unknown,
+            "se ha detectado un deadlock",
+        )));
+        assert!(!super::is_retryable(&error(
+            DatabaseErrorKind::UniqueViolation,
+            "UNIQUE constraint failed: users.name",
+        )));
+        assert!(!super::is_retryable(&Error::NotFound));
+    }
+
+    #[test]
+    fn it_imports_rows_with_their_ids() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let inserted = MemberWithId {
+            id: 42,
+            name: String::from("Ferris"),
+        }
+        .import(&mut conn)
+        .unwrap();
+
+        assert_eq!(inserted, 1);
+
+        let member: MemberWithId = users::table.first(&mut conn).unwrap();
+        assert_eq!((member.id, member.name), (42, String::from("Ferris")));
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(with_id)]
+    #[derive(Debug, Queryable, Identifiable)]
+    #[diesel(table_name = users)]
+    pub struct Archived {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[test]
+    fn it_imports_rows_of_identifiable_structs() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let inserted = ArchivedWithId {
+            id: 7,
+            name: String::from("Ferris"),
+        }
+        .import(&mut conn)
+        .unwrap();
+        assert_eq!(inserted, 1);
+
+        let archived: Archived = users::table.first(&mut conn).unwrap();
+        assert_eq!(*archived.id(), 7);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn it_records_insert_metrics_per_table() {
+        use super::InsertNew;
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            NewMember {
+                name: String::from("Ferris"),
+            }
+            .insert(&mut conn)
+            .unwrap();
+        });
+
+        let inserts = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .find(|(key, ..)| key.key().name() == "diesel_new_inserts_total")
+            .unwrap();
+        let labels = inserts
+            .0
+            .key()
+            .labels()
+            .map(|label| (label.key().to_string(), label.value().to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            labels,
+            [
+                (String::from("table"), String::from("users")),
+                (String::from("result"), String::from("ok"))
+            ]
+        );
+        assert_eq!(inserts.3, DebugValue::Counter(1));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn it_records_insert_metrics_with_the_table_as_diesel_names_it() {
+        use super::InsertNew;
+        use metrics_util::debugging::DebuggingRecorder;
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("ATTACH DATABASE ':memory:' AS auth")
+            .execute(&mut conn)
+            .unwrap();
+        diesel::sql_query("CREATE TABLE auth.people (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            NewPerson {
+                name: String::from("Ferris"),
+            }
+            .insert(&mut conn)
+            .unwrap();
+        });
+
+        let tables = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .flat_map(|(key, ..)| key.key().labels().cloned().collect::<Vec<_>>())
+            .filter(|label| label.key() == "table")
+            .map(|label| label.value().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(tables, ["auth.people", "auth.people"]);
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(example_attr = schemars)]
+    #[derive(Queryable, schemars::JsonSchema)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Subscriber {
+        id: i32,
+        #[new(example = "Ferris")]
+        name: String,
+    }
+
+    #[test]
+    fn it_adds_examples_to_the_schema() {
+        let schema = schemars::schema_for!(NewSubscriber);
+
+        assert_eq!(
+            schema.as_value()["properties"]["name"]["examples"],
+            serde_json::json!(["Ferris"])
+        );
+    }
+
+    #[cfg(feature = "form")]
+    #[apply(NewInsertable!)]
+    #[new(from_form)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = accounts)]
+    #[allow(dead_code)]
+    pub struct Registration {
+        id: i32,
+        email: String,
+        name: Option<String>,
+        admin: bool,
+    }
+
+    #[cfg(feature = "form")]
+    #[test]
+    fn it_converts_form_data_into_a_new_struct() {
+        use std::collections::HashMap;
+
+        let form = HashMap::from([
+            (String::from("email"), String::from("ferris@example.com")),
+            (String::from("name"), String::new()),
+            (String::from("admin"), String::from("false")),
+        ]);
+
+        let new = NewRegistration::try_from(&form).unwrap();
+        assert_eq!(new.email, "ferris@example.com");
+        assert_eq!(new.name, None);
+        assert!(!new.admin);
+
+        let form = HashMap::from([(String::from("admin"), String::from("yes"))]);
+
+        assert_eq!(
+            NewRegistration::try_from(&form).unwrap_err().to_string(),
+            "field `email` is missing; field `admin` is not a valid `bool`: provided string was not `true` or `false`"
+        );
+    }
+
+    #[test]
+    fn it_inserts_with_raw_sql() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_eq!(
+            NewMember::raw_insert_sql::<diesel::pg::Pg>().unwrap(),
+            r#"INSERT INTO "users" ("name") VALUES ($1)"#
+        );
+
+        let sql = NewMember::raw_insert_sql::<diesel::sqlite::Sqlite>().unwrap();
+        assert_eq!(sql, "INSERT INTO `users` (`name`) VALUES (?)");
+
+        NewMember {
+            name: String::from("Ferris"),
+        }
+        .bind_raw_insert(diesel::sql_query(sql))
+        .execute(&mut conn)
+        .unwrap();
+
+        let names = users::table
+            .select(users::name)
+            .load::<String>(&mut conn)
+            .unwrap();
+        assert_eq!(names, [String::from("Ferris")]);
+    }
+
+    table! {
+        #[sql_name = "people"]
+        auth.persons(id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    // Named differently in the database, and in a schema of its own
+    #[apply(NewInsertable!)]
+    #[new(raw_insert, verify_schema)]
+    #[derive(Queryable)]
+    #[diesel(table_name = persons)]
+    #[allow(dead_code)]
+    pub struct Person {
+        id: i32,
+        pub name: String,
+    }
+
+    #[test]
+    fn it_inserts_with_raw_sql_into_the_table_as_diesel_names_it() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("ATTACH DATABASE ':memory:' AS auth")
+            .execute(&mut conn)
+            .unwrap();
+        diesel::sql_query("CREATE TABLE auth.people (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_eq!(
+            NewPerson::raw_insert_sql::<diesel::pg::Pg>().unwrap(),
+            r#"INSERT INTO "auth"."people" ("name") VALUES ($1)"#
+        );
+
+        let sql = NewPerson::raw_insert_sql::<diesel::sqlite::Sqlite>().unwrap();
+        assert_eq!(sql, "INSERT INTO `auth`.`people` (`name`) VALUES (?)");
+
+        NewPerson {
+            name: String::from("Ferris"),
+        }
+        .bind_raw_insert(diesel::sql_query(sql))
+        .execute(&mut conn)
+        .unwrap();
+
+        let names = persons::table
+            .select(persons::name)
+            .load::<String>(&mut conn)
+            .unwrap();
+        assert_eq!(names, [String::from("Ferris")]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn it_verifies_the_schema_of_the_table_as_diesel_names_it() {
+        use super::schema::SchemaMismatch;
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("ATTACH DATABASE ':memory:' AS auth")
+            .execute(&mut conn)
+            .unwrap();
+        diesel::sql_query("CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        assert!(matches!(
+            NewPerson::verify_schema(&mut conn),
+            Err(SchemaMismatch::MissingTable { table }) if table == "auth.people"
+        ));
+
+        diesel::sql_query("CREATE TABLE auth.people (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        assert!(NewPerson::verify_schema(&mut conn).is_ok());
+    }
+
+    // The struct itself can't be serialized, only the payload that is queued
+    #[apply(NewInsertable!)]
+    #[new(
+        attr(derive(serde::Serialize, serde::Deserialize)),
+        attr(derive(PartialEq))
+    )]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Enrollment {
+        id: i32,
+        pub name: String,
+    }
+
+    #[test]
+    fn it_adds_attributes_to_the_new_struct_only() {
+        let new = NewEnrollment {
+            name: String::from("Ferris"),
+        };
+        let payload = serde_json::to_string(&new).unwrap();
+
+        assert_eq!(payload, r#"{"name":"Ferris"}"#);
+        assert_eq!(
+            serde_json::from_str::<NewEnrollment>(&payload).unwrap(),
+            new
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[apply(NewInsertable!)]
+    #[new(event, create)]
+    #[derive(Debug, Clone, Queryable, serde::Serialize)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Follower {
+        id: i32,
+        /// Shows up on their profile
+        #[serde(rename = "display_name")]
+        pub name: String,
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_generates_a_created_event() {
+        let event = FollowerCreatedEvent::new(
+            42,
+            NewFollower {
+                name: String::from("Ferris"),
+            },
+        );
+
+        assert_eq!(
+            r#"{"id":42,"display_name":"Ferris"}"#,
+            serde_json::to_string(&event).unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_creates_the_row_with_its_event() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let (follower, event) = NewFollower {
+            name: String::from("Ferris"),
+        }
+        .create_with_event(&mut conn)
+        .unwrap();
+
+        assert_eq!(follower.id, 1);
+        assert_eq!(event.id, follower.id);
+        assert_eq!(event.name, "Ferris");
+    }
+
+    // The struct has methods of its own with the default names
+    #[apply(NewInsertable!)]
+    #[new(builder, update_from_new, rename(builder = create, update_from_new = replace))]
+    #[derive(Queryable, AsChangeset)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Editor {
+        id: i32,
+        pub name: String,
+    }
+
+    #[allow(dead_code)]
+    impl Editor {
+        fn update_from_new(&self) {}
+    }
+
+    #[test]
+    fn it_renames_generated_methods() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        NewEditor::create()
+            .name("Ferris")
+            .build()
+            .insert_into(users::table)
+            .execute(&mut conn)
+            .unwrap();
+
+        let editor =
+            Editor::replace(1, NewEditor::create().name("Corro").build(), &mut conn).unwrap();
+
+        assert_eq!(editor.name, "Corro");
+    }
+
+    #[apply(NewInsertable!)]
+    #[new(update_from_new)]
+    #[derive(Queryable, AsChangeset)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Maintainer {
+        id: i32,
+        pub name: String,
+    }
+
+    #[test]
+    fn it_updates_every_column_from_a_new_struct() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        NewMaintainer {
+            name: String::from("Ferris"),
+        }
+        .insert_into(users::table)
+        .execute(&mut conn)
+        .unwrap();
+
+        let maintainer = Maintainer::update_from_new(
+            1,
+            NewMaintainer {
+                name: String::from("Corro"),
+            },
+            &mut conn,
+        )
+        .unwrap();
+
+        assert_eq!(maintainer.name, "Corro");
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        diesel_derive_newtype::DieselNewType,
+        serde::Serialize,
+        serde::Deserialize,
+    )]
+    pub struct CustomerId(i32);
+
+    #[cfg(feature = "serde")]
+    impl super::AutoincrementId for CustomerId {}
+
+    #[cfg(feature = "serde")]
+    #[apply(NewInsertable!)]
+    #[new(event, update_from_new, check_id)]
+    #[derive(Clone, Queryable, AsChangeset)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Customer {
+        id: CustomerId,
+        pub name: String,
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_uses_newtype_ids() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let new = NewCustomer {
+            name: String::from("Ferris"),
+        };
+
+        let id = diesel::insert_into(users::table)
+            .values(new.clone())
+            .returning(users::id)
+            .get_result(&mut conn)
+            .unwrap();
+
+        let event = CustomerCreatedEvent::new(id, new);
+        assert_eq!(event.id, CustomerId(1));
+
+        let customer = Customer::update_from_new(
+            event.id,
+            NewCustomer {
+                name: String::from("Corro"),
+            },
+            &mut conn,
+        )
+        .unwrap();
+
+        assert_eq!(customer.id, CustomerId(1));
+    }
+
+    #[test]
+    fn it_inserts_through_the_extension_trait() {
+        use super::InsertNew;
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+
+        let user: SuperUser = NewSuperUser {
+            name: String::from("Ferris"),
+        }
+        .insert_returning(&mut conn)
+        .unwrap();
+
+        assert_eq!(user.id, 1);
+        assert_eq!(user.name, "Ferris");
+    }
+
+    table! {
+        wides(id) {
+            id -> Integer,
+            c01 -> Integer,
+            c02 -> Integer,
+            c03 -> Integer,
+            c04 -> Integer,
+            c05 -> Integer,
+            c06 -> Integer,
+            c07 -> Integer,
+            c08 -> Integer,
+            c09 -> Integer,
+            c10 -> Integer,
+            c11 -> Integer,
+            c12 -> Integer,
+            c13 -> Integer,
+            c14 -> Integer,
+            c15 -> Integer,
+            c16 -> Integer,
+            c17 -> Integer,
+            c18 -> Integer,
+            c19 -> Integer,
+            c20 -> Integer,
+            c21 -> Integer,
+            c22 -> Integer,
+            c23 -> Integer,
+            c24 -> Integer,
+            c25 -> Integer,
+            c26 -> Integer,
+            c27 -> Integer,
+            c28 -> Integer,
+            c29 -> Integer,
+            c30 -> Integer,
+            c31 -> Integer,
+        }
+    }
+
+    // Every column allowed by Diesel's default features, with docs and the
+    // options that go over every field, to catch hitting the recursion limit
+    #[cfg(feature = "serde")]
+    #[apply(NewInsertable!)]
+    #[new(builder, event, display)]
+    #[derive(Default, Queryable, serde::Serialize)]
+    #[diesel(table_name = wides)]
+    #[allow(dead_code)]
+    pub struct Wide {
+        id: i32,
+        /// Column 1
+        pub c01: i32,
+        /// Column 2
+        pub c02: i32,
+        /// Column 3
+        pub c03: i32,
+        /// Column 4
+        pub c04: i32,
+        /// Column 5
+        pub c05: i32,
+        /// Column 6
+        pub c06: i32,
+        /// Column 7
+        pub c07: i32,
+        /// Column 8
+        pub c08: i32,
+        /// Column 9
+        pub c09: i32,
+        /// Column 10
+        pub c10: i32,
+        /// Column 11
+        pub c11: i32,
+        /// Column 12
+        pub c12: i32,
+        /// Column 13
+        pub c13: i32,
+        /// Column 14
+        pub c14: i32,
+        /// Column 15
+        pub c15: i32,
+        /// Column 16
+        pub c16: i32,
+        /// Column 17
+        pub c17: i32,
+        /// Column 18
+        pub c18: i32,
+        /// Column 19
+        pub c19: i32,
+        /// Column 20
+        pub c20: i32,
+        /// Column 21
+        pub c21: i32,
+        /// Column 22
+        pub c22: i32,
+        /// Column 23
+        pub c23: i32,
+        /// Column 24
+        pub c24: i32,
+        /// Column 25
+        pub c25: i32,
+        /// Column 26
+        pub c26: i32,
+        /// Column 27
+        pub c27: i32,
+        /// Column 28
+        pub c28: i32,
+        /// Column 29
+        pub c29: i32,
+        /// Column 30
+        pub c30: i32,
+        /// Column 31
+        pub c31: i32,
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_generates_a_new_struct_for_a_wide_table() {
+        assert_eq!(NewWide::default().c31, 0);
+    }
+
+    #[test]
+    fn it_snapshots_the_generated_fields() {
+        let snapshot = super::snapshot_new_struct! {
+            #[new(manual_insertable)]
+            #[derive(Queryable)]
+            pub struct Post {
+                /// The ID
+                id: i32,
+                #[diesel(column_name = post_title)]
+                pub title: String,
+                body: Option<String>,
+            }
+        };
+
+        assert_eq!(snapshot, &[("title", "String"), ("body", "Option<String>")]);
+    }
+
+    #[cfg(feature = "test-support")]
+    table! {
+        drafts(id) {
+            id -> Integer,
+            title -> Text,
+            words -> BigInt,
+            published -> Bool,
+            summary -> Nullable<Text>,
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[apply(NewInsertable!)]
+    #[new(graphql(pool = diesel_async::pooled_connection::deadpool::Pool<diesel_async::AsyncPgConnection>))]
+    #[new(attr(derive(async_graphql::InputObject)))]
+    #[derive(Queryable, async_graphql::SimpleObject)]
+    #[diesel(table_name = users)]
+    pub struct Speaker {
+        id: i32,
+        pub name: String,
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn it_generates_a_graphql_mutation() {
+        struct Query;
+
+        #[async_graphql::Object]
+        impl Query {
+            async fn version(&self) -> i32 {
+                1
+            }
+        }
+
+        let schema =
+            async_graphql::Schema::build(Query, SpeakerMutation, async_graphql::EmptySubscription)
+                .finish();
+
+        assert!(schema
+            .sdl()
+            .contains("createSpeaker(input: NewSpeaker!): Speaker!"));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn it_inserts_with_an_async_connection() {
+        use super::InsertNewAsync;
+        use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+        use diesel_async::{AsyncConnection, SimpleAsyncConnection};
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let mut conn = SyncConnectionWrapper::<SqliteConnection>::establish(":memory:")
+                .await
+                .unwrap();
+            conn.batch_execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+                .await
+                .unwrap();
+
+            let inserted = NewSuperUser {
+                name: String::from("Ferris"),
+            }
+            .insert_async(&mut conn)
+            .await
+            .unwrap();
+            assert_eq!(inserted, 1);
+
+            let user: SuperUser = NewSuperUser {
+                name: String::from("Corro"),
+            }
+            .insert_returning_async(&mut conn)
+            .await
+            .unwrap();
+            assert_eq!(user.id, 2);
+            assert_eq!(user.name, "Corro");
+        });
+    }
+
+    // Records the number of rows in each insert, as Diesel only batches inserts
+    // into a single query for Postgres with an async connection
+    #[cfg(feature = "async")]
+    #[derive(Default)]
+    struct CountingConnection {
+        batches: Vec<usize>,
+    }
+
+    #[cfg(feature = "async")]
+    impl diesel_async::SimpleAsyncConnection for CountingConnection {
+        fn batch_execute(
+            &mut self,
+            _query: &str,
+        ) -> impl std::future::Future<Output = QueryResult<()>> + Send {
+            std::future::ready(Ok(()))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl diesel_async::AsyncConnectionCore for CountingConnection {
+        type ExecuteFuture<'conn, 'query> = std::future::Ready<QueryResult<usize>>;
+        type LoadFuture<'conn, 'query> =
+            std::future::Ready<QueryResult<Self::Stream<'conn, 'query>>>;
+        type Stream<'conn, 'query> =
+            futures_util::stream::Empty<QueryResult<Self::Row<'conn, 'query>>>;
+        type Row<'conn, 'query> =
+            <diesel_async::AsyncPgConnection as diesel_async::AsyncConnectionCore>::Row<
+                'conn,
+                'query,
+            >;
+        type Backend = diesel::pg::Pg;
+
+        fn load<'conn, 'query, T>(&'conn mut self, _source: T) -> Self::LoadFuture<'conn, 'query>
+        where
+            T: diesel::query_builder::AsQuery + 'query,
+            T::Query: diesel::query_builder::QueryFragment<Self::Backend>
+                + diesel::query_builder::QueryId
+                + 'query,
+        {
+            std::future::ready(Ok(futures_util::stream::empty()))
+        }
+
+        fn execute_returning_count<'conn, 'query, T>(
+            &'conn mut self,
+            source: T,
+        ) -> Self::ExecuteFuture<'conn, 'query>
+        where
+            T: diesel::query_builder::QueryFragment<Self::Backend>
+                + diesel::query_builder::QueryId
+                + 'query,
+        {
+            let sql = debug_query::<diesel::pg::Pg, _>(&source).to_string();
+            let rows = sql.matches("($").count();
+            self.batches.push(rows);
+
+            std::future::ready(Ok(rows))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn it_ingests_a_stream_in_chunks() {
+        use super::InsertNewAsync;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let rows = || {
+                futures_util::stream::iter((1..=5).map(|i| NewSuperUser {
+                    name: format!("Ferris {}", i),
+                }))
+            };
+
+            let mut conn = CountingConnection::default();
+            let inserted = NewSuperUser::ingest(rows(), 2, &mut conn).await.unwrap();
+            assert_eq!(inserted, 5);
+            assert_eq!(conn.batches, [2, 2, 1]);
+
+            let mut conn = CountingConnection::default();
+            let inserted = NewSuperUser::ingest(rows(), 0, &mut conn).await.unwrap();
+            assert_eq!(inserted, 5);
+            assert_eq!(conn.batches, [1, 1, 1, 1, 1]);
+        });
+    }
+
+    #[cfg(feature = "wasm")]
+    #[apply(NewInsertable!)]
+    #[new(wasm)]
+    #[derive(Debug, Queryable)]
+    #[diesel(table_name = users)]
+    #[allow(dead_code)]
+    pub struct Visitor {
+        id: i32,
+        pub name: String,
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn it_exports_the_new_struct_to_wasm() {
+        fn exported<T: wasm_bindgen::convert::IntoWasmAbi>() {}
+
+        exported::<NewVisitor>();
+
+        let query = NewVisitor {
+            name: String::from("Ferris"),
+        }
+        .insert_into(users::table);
+
+        assert_eq!(
+            r#"INSERT INTO `users` (`name`) VALUES (?) -- binds: ["Ferris"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    // Generates `draft_round_trips_random_inserts_through_sqlite`
+    #[cfg(feature = "test-support")]
+    #[apply(NewInsertable!)]
+    #[new(proptest)]
+    #[derive(Queryable)]
+    #[diesel(table_name = drafts)]
+    #[allow(dead_code)]
+    pub struct Draft {
+        id: i32,
+        title: String,
+        words: i64,
+        published: bool,
+        summary: Option<String>,
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn it_round_trips_through_sqlite() {
+        table! {
+            posts(id) {
+                id -> Integer,
+                title -> Text,
+                views -> BigInt,
+                body -> Nullable<Text>,
+            }
+        }
+
+        #[apply(NewInsertable!)]
+        #[derive(Debug, Queryable)]
+        #[diesel(table_name = posts)]
+        #[allow(dead_code)]
+        pub struct Post {
+            id: i32,
+            title: String,
+            views: i64,
+            body: Option<String>,
+        }
+
+        super::verify_insert_roundtrip!(
+            Post,
+            NewPost {
+                title: String::from("Hello"),
+                views: 42,
+                body: None,
+            },
+            posts::table
+        );
+    }
+
+    #[test]
+    fn it_generates_a_new_struct() {
+        let _ = NewUser {
+            name: String::from("Ferris"),
+        };
+    }
+
+    #[test]
+    fn it_works_with_the_apply_attr_and_identifiable() {
+        let _ = NewSuperUser {
+            name: String::from("Ferris"),
+        };
+    }
+
+    #[test]
+    fn it_leaves_identifiable_off_the_new_struct() {
+        let new_moderator = NewModerator {
+            name: String::from("Ferris"),
+        };
+        assert_eq!(
+            format!("{new_moderator:?}"),
+            r#"NewModerator { name: "Ferris" }"#
+        );
+    }
+
+    #[test]
+    fn it_replaces_the_derives_of_the_new_struct() {
+        let new_reviewer = NewReviewer {
+            name: String::from("Ferris"),
+        };
+        assert_eq!(new_reviewer.clone(), new_reviewer);
+        assert_eq!(
+            format!("{new_reviewer:?}"),
+            r#"NewReviewer { name: "Ferris" }"#
+        );
+
+        let query = new_reviewer.insert_into(users::table);
+        assert_eq!(
+            r#"INSERT INTO `users` (`name`) VALUES (?) -- binds: ["Ferris"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
+    }
+
+    #[test]
+    fn it_uses_a_hand_written_insertable_impl() {
+        let query = NewShoutyUser {
+            name: String::from("Ferris"),
+        }
+        .insert_into(users::table);
+
+        assert_eq!(
+            r#"INSERT INTO `users` (`name`) VALUES (?) -- binds: ["FERRIS"]"#,
+            debug_query::<diesel::sqlite::Sqlite, _>(&query).to_string()
+        );
     }
 
     #[test]
@@ -0,0 +1,46 @@
+//! Raw SQL for New structs, for the rare inserts that Diesel's query builder
+//! can't express (hints, unsupported clauses etc.)
+
+use diesel::backend::Backend;
+use diesel::query_builder::QueryBuilder;
+use diesel::QueryResult;
+
+use crate::table_name::TableName;
+
+/// `INSERT INTO table (columns) VALUES (placeholders)`, with the table, the
+/// columns and the placeholders written by `DB`'s query builder
+#[doc(hidden)]
+pub fn insert_sql<T, DB>(columns: &[&str]) -> QueryResult<String>
+where
+    T: TableName<DB>,
+    DB: Backend,
+    DB::QueryBuilder: Default,
+{
+    let mut out = DB::QueryBuilder::default();
+    out.push_sql("INSERT INTO ");
+    T::push_name(&mut out)?;
+
+    if columns.is_empty() {
+        out.push_sql(" DEFAULT VALUES");
+        return Ok(out.finish());
+    }
+
+    out.push_sql(" (");
+    for (i, column) in columns.iter().enumerate() {
+        if i > 0 {
+            out.push_sql(", ");
+        }
+        out.push_identifier(column)?;
+    }
+
+    out.push_sql(") VALUES (");
+    for i in 0..columns.len() {
+        if i > 0 {
+            out.push_sql(", ");
+        }
+        out.push_bind_param();
+    }
+    out.push_sql(")");
+
+    Ok(out.finish())
+}
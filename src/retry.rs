@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::{Connection, QueryResult};
+
+/// How many times, and how often, [`with_retry`] tries again after an error
+/// that is worth retrying
+///
+/// The default is 3 attempts in total, waiting 10ms after the first failure and
+/// twice as long after each one after that, up to 1s, on the errors that
+/// [`is_retryable`].
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    classify: fn(&Error) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            classify: is_retryable,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Tries at most `max_attempts` times in total, including the first one
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait after the first failure, doubling after each one after
+    /// that
+    pub fn backoff(self, backoff: Duration) -> Self {
+        Self { backoff, ..self }
+    }
+
+    /// The longest to wait between two attempts
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        Self {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Which errors are worth retrying, instead of [`is_retryable`]
+    ///
+    /// For example, to only retry serialization failures, where another backend
+    /// can fail with a `deadlock detected` message that isn't a deadlock:
+    ///
+    /// ```rust
+    /// use diesel::result::{DatabaseErrorKind, Error};
+    /// use diesel_autoincrement_new_struct::RetryPolicy;
+    ///
+    /// let policy = RetryPolicy::default().classify(|error| {
+    ///     matches!(
+    ///         error,
+    ///         Error::DatabaseError(DatabaseErrorKind::SerializationFailure, _)
+    ///     )
+    /// });
+    /// ```
+    pub fn classify(self, classify: fn(&Error) -> bool) -> Self {
+        Self { classify, ..self }
+    }
+}
+
+/// Whether an error is a serialization failure or a deadlock, where the
+/// transaction was rolled back by the database and can simply be tried again
+///
+/// MySQL deadlocks already come back from Diesel as serialization failures.
+/// Diesel has no kind for Postgres deadlocks and doesn't expose their SQLSTATE
+/// (`40P01`), so they are only recognised by their `deadlock detected`
+/// message, which Postgres translates when `lc_messages` isn't English. With
+/// another language, deadlocks aren't retried. The message is matched whatever
+/// the backend, so use [`RetryPolicy::classify`] where another database can
+/// fail with a `deadlock detected` message that isn't worth retrying.
+pub fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::DatabaseError(DatabaseErrorKind::SerializationFailure, _) => true,
+        Error::DatabaseError(_, info) => info.message().contains("deadlock detected"),
+        _ => false,
+    }
+}
+
+/// Runs `f` in a transaction, and runs it again in a new transaction for as
+/// long as it fails with an error that the policy classifies as retryable
+/// ([`is_retryable`] by default) and it has attempts left
+///
+/// This has to be called outside of any other transaction, as the database
+/// rolls back the whole transaction on these errors. The transaction runs at
+/// the connection's default isolation level; on Postgres, `f` can start with
+/// `SET TRANSACTION ISOLATION LEVEL ...` to run at another one. Use `#[new(retry)]` to
+/// generate `NewStruct::create_with_retry(conn, policy)`, which inserts with
+/// this.
+///
+/// # Example
+///
+/// ```rust
+/// use diesel_autoincrement_new_struct::{with_retry, RetryPolicy};
+/// use diesel::prelude::*;
+///
+/// let mut conn = SqliteConnection::establish(":memory:").unwrap();
+///
+/// let answer = with_retry(&mut conn, &RetryPolicy::default(), |conn| {
+///     diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("42")).get_result::<i32>(conn)
+/// });
+/// assert_eq!(answer, Ok(42));
+/// ```
+pub fn with_retry<Conn, T, F>(conn: &mut Conn, policy: &RetryPolicy, mut f: F) -> QueryResult<T>
+where
+    Conn: Connection,
+    F: FnMut(&mut Conn) -> QueryResult<T>,
+{
+    let mut attempts = 1;
+    let mut backoff = policy.backoff;
+
+    loop {
+        match conn.transaction(&mut f) {
+            Err(error) if attempts < policy.max_attempts && (policy.classify)(&error) => {
+                std::thread::sleep(backoff);
+                attempts += 1;
+                backoff = (backoff * 2).min(policy.max_backoff);
+            }
+            result => return result,
+        }
+    }
+}
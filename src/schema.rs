@@ -0,0 +1,220 @@
+//! Checking New structs against the schema of a live database, for catching
+//! deployments where the migrations lag behind the code
+
+use std::error::Error;
+use std::fmt;
+
+use diesel::backend::Backend;
+use diesel::query_builder::SqlQuery;
+use diesel::query_dsl::LoadQuery;
+use diesel::sql_types::{Bool, Text};
+use diesel::{Connection, QueryableByName, RunQueryDsl};
+
+use crate::table_name::TableName;
+
+/// Backends whose columns `verify_schema` can query, which are Postgres, MySQL
+/// and SQLite with the features of the same name
+pub trait SchemaBackend: Backend {
+    /// SQL selecting the `column_name` and whether each column is `nullable`,
+    /// for every column of `table` in `schema`, or in the schema that queries
+    /// use by default
+    fn columns_sql(schema: Option<&str>, table: &str) -> String;
+}
+
+/// `'name'`, as the names of the table and its schema come from `table!`, so
+/// they are written into the query rather than bound
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn literal(name: &str) -> String {
+    format!("'{}'", name.replace('\'', "''"))
+}
+
+#[cfg(feature = "postgres")]
+impl SchemaBackend for diesel::pg::Pg {
+    fn columns_sql(schema: Option<&str>, table: &str) -> String {
+        format!(
+            "SELECT column_name::text AS column_name, is_nullable = 'YES' AS nullable \
+             FROM information_schema.columns \
+             WHERE table_schema = {} AND table_name = {}",
+            schema.map_or_else(|| String::from("current_schema()"), literal),
+            literal(table)
+        )
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl SchemaBackend for diesel::mysql::Mysql {
+    fn columns_sql(schema: Option<&str>, table: &str) -> String {
+        format!(
+            "SELECT column_name AS column_name, is_nullable = 'YES' AS nullable \
+             FROM information_schema.columns \
+             WHERE table_schema = {} AND table_name = {}",
+            schema.map_or_else(|| String::from("DATABASE()"), literal),
+            literal(table)
+        )
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl SchemaBackend for diesel::sqlite::Sqlite {
+    fn columns_sql(schema: Option<&str>, table: &str) -> String {
+        let schema = schema.map(|schema| format!(", {}", literal(schema)));
+
+        format!(
+            "SELECT name AS column_name, \"notnull\" = 0 AS nullable FROM pragma_table_info({}{})",
+            literal(table),
+            schema.unwrap_or_default()
+        )
+    }
+}
+
+/// A column of a New struct that doesn't match the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnMismatch {
+    /// The column isn't in the table
+    Missing {
+        /// Name of the column
+        column: &'static str,
+    },
+    /// The field is an `Option` but the column is `NOT NULL`, or the other way
+    /// around
+    Nullability {
+        /// Name of the column
+        column: &'static str,
+        /// Whether the field is an `Option`
+        nullable: bool,
+    },
+}
+
+impl fmt::Display for ColumnMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnMismatch::Missing { column } => write!(f, "column `{}` is missing", column),
+            ColumnMismatch::Nullability {
+                column,
+                nullable: true,
+            } => write!(
+                f,
+                "column `{}` is `NOT NULL`, but the field is optional",
+                column
+            ),
+            ColumnMismatch::Nullability {
+                column,
+                nullable: false,
+            } => write!(f, "column `{}` is nullable, but the field isn't", column),
+        }
+    }
+}
+
+/// Error from `NewStruct::verify_schema(conn)`
+#[derive(Debug)]
+pub enum SchemaMismatch {
+    /// The columns of the table couldn't be queried
+    Query(diesel::result::Error),
+    /// The table isn't in the database
+    MissingTable {
+        /// Name of the table, with its schema if it has one
+        table: String,
+    },
+    /// Columns of the table that don't match, in the order of the struct
+    Columns {
+        /// Name of the table, with its schema if it has one
+        table: String,
+        /// Every column that doesn't match, rather than just the first one
+        columns: Vec<ColumnMismatch>,
+    },
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaMismatch::Query(error) => write!(f, "failed to query the schema: {}", error),
+            SchemaMismatch::MissingTable { table } => write!(f, "table `{}` is missing", table),
+            SchemaMismatch::Columns { table, columns } => {
+                write!(f, "table `{}` doesn't match: ", table)?;
+
+                for (i, column) in columns.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+
+                    write!(f, "{}", column)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for SchemaMismatch {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SchemaMismatch::Query(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for SchemaMismatch {
+    fn from(error: diesel::result::Error) -> Self {
+        SchemaMismatch::Query(error)
+    }
+}
+
+/// A column of a table, as it is in the database
+#[doc(hidden)]
+#[derive(Debug, QueryableByName)]
+pub struct TableColumn {
+    #[diesel(sql_type = Text)]
+    column_name: String,
+    #[diesel(sql_type = Bool)]
+    nullable: bool,
+}
+
+/// Compares `(column, nullable)` pairs against the columns of the table `T`
+#[doc(hidden)]
+pub fn verify<T, Conn>(
+    conn: &mut Conn,
+    columns: &[(&'static str, bool)],
+) -> Result<(), SchemaMismatch>
+where
+    T: TableName<Conn::Backend>,
+    Conn: Connection,
+    Conn::Backend: SchemaBackend,
+    <Conn::Backend as Backend>::QueryBuilder: Default,
+    SqlQuery: for<'query> LoadQuery<'query, Conn, TableColumn>,
+{
+    let (schema, name) = crate::table_name::schema_and_name::<T, Conn::Backend>()?;
+    let sql = Conn::Backend::columns_sql(schema.as_deref(), &name);
+    let table = match schema {
+        Some(schema) => format!("{}.{}", schema, name),
+        None => name,
+    };
+
+    let actual = diesel::sql_query(sql).load::<TableColumn>(conn)?;
+    if actual.is_empty() {
+        return Err(SchemaMismatch::MissingTable { table });
+    }
+
+    let mismatches = columns
+        .iter()
+        .filter_map(|&(column, nullable)| {
+            match actual.iter().find(|actual| actual.column_name == column) {
+                None => Some(ColumnMismatch::Missing { column }),
+                Some(actual) if actual.nullable != nullable => {
+                    Some(ColumnMismatch::Nullability { column, nullable })
+                }
+                Some(_) => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaMismatch::Columns {
+            table,
+            columns: mismatches,
+        })
+    }
+}
@@ -0,0 +1,174 @@
+//! Names of tables as Diesel writes them in queries, which takes
+//! `#[sql_name]` and the schema of the table into account
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+use diesel::backend::Backend;
+use diesel::query_builder::{QueryBuilder, QueryFragment};
+use diesel::{QueryResult, Table};
+
+/// The name of a table as Diesel writes it in queries for `DB`, which takes
+/// its `#[sql_name]` and schema into account
+///
+/// This is implemented for every table from `table!` with every backend. Code
+/// that is generic over New structs only has to spell it out for
+/// `raw_insert_sql` and `verify_schema`, and otherwise uses
+/// [`InstrumentedTable`] for inserts.
+pub trait TableName<DB: Backend> {
+    /// Writes the name of the table, quoted and qualified with its schema if
+    /// it has one, e.g. `"auth"."users"`
+    fn push_name(out: &mut DB::QueryBuilder) -> QueryResult<()>;
+
+    /// The name of the table without the quotes, and with its schema if it has
+    /// one, e.g. `auth.users`, which is only worked out once
+    fn name() -> &'static str;
+}
+
+impl<T, DB> TableName<DB> for T
+where
+    T: Table + Default + 'static,
+    T::FromClause: QueryFragment<DB>,
+    DB: Backend + Default + 'static,
+    DB::QueryBuilder: Default,
+{
+    fn push_name(out: &mut DB::QueryBuilder) -> QueryResult<()> {
+        T::default().from_clause().to_sql(out, &DB::default())
+    }
+
+    fn name() -> &'static str {
+        // Statics are shared by every instance of a generic function
+        static NAMES: OnceLock<RwLock<HashMap<TypeId, &'static str>>> = OnceLock::new();
+
+        let names = NAMES.get_or_init(Default::default);
+        let key = TypeId::of::<(T, DB)>();
+
+        // Every insert of a table after the first only reads the name
+        if let Some(name) = names
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            return name;
+        }
+
+        names
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key)
+            .or_insert_with(|| match schema_and_name::<T, DB>() {
+                Ok((Some(schema), name)) => {
+                    Box::leak(format!("{}.{}", schema, name).into_boxed_str())
+                }
+                Ok((None, name)) => Box::leak(name.into_boxed_str()),
+                // Only backends that reject the name of the table fail to write it
+                Err(_) => std::any::type_name::<T>(),
+            })
+    }
+}
+
+/// The tables that inserts through this crate can be made into, which is
+/// every [`TableName`] with the `metrics` feature, as inserts are recorded
+/// with the name of their table, and every type without it
+///
+/// This only has to be spelled out by code that is generic over New structs,
+/// along with the bounds on the insert statement:
+///
+/// ```rust
+/// # use diesel::associations::HasTable;
+/// # use diesel::prelude::*;
+/// # use diesel::query_builder::InsertStatement;
+/// # use diesel::query_dsl::methods::ExecuteDsl;
+/// # use diesel_autoincrement_new_struct::{InsertNew, InstrumentedTable};
+/// fn insert<N>(new: N, conn: &mut SqliteConnection) -> QueryResult<usize>
+/// where
+///     N: InsertNew,
+///     N::Table: InstrumentedTable<diesel::sqlite::Sqlite>,
+///     InsertStatement<N::Table, N::Values>: ExecuteDsl<SqliteConnection>,
+/// {
+///     new.insert(conn)
+/// }
+/// ```
+#[cfg(feature = "metrics")]
+pub trait InstrumentedTable<DB: Backend>: TableName<DB> {}
+
+#[cfg(feature = "metrics")]
+impl<T: TableName<DB>, DB: Backend> InstrumentedTable<DB> for T {}
+
+/// The tables that inserts through this crate can be made into, which is
+/// every [`TableName`] with the `metrics` feature, as inserts are recorded
+/// with the name of their table, and every type without it
+///
+/// This only has to be spelled out by code that is generic over New structs,
+/// along with the bounds on the insert statement:
+///
+/// ```rust
+/// # use diesel::associations::HasTable;
+/// # use diesel::prelude::*;
+/// # use diesel::query_builder::InsertStatement;
+/// # use diesel::query_dsl::methods::ExecuteDsl;
+/// # use diesel_autoincrement_new_struct::{InsertNew, InstrumentedTable};
+/// fn insert<N>(new: N, conn: &mut SqliteConnection) -> QueryResult<usize>
+/// where
+///     N: InsertNew,
+///     N::Table: InstrumentedTable<diesel::sqlite::Sqlite>,
+///     InsertStatement<N::Table, N::Values>: ExecuteDsl<SqliteConnection>,
+/// {
+///     new.insert(conn)
+/// }
+/// ```
+#[cfg(not(feature = "metrics"))]
+pub trait InstrumentedTable<DB: Backend> {}
+
+#[cfg(not(feature = "metrics"))]
+impl<T: ?Sized, DB: Backend> InstrumentedTable<DB> for T {}
+
+/// The schema of `T`, if it has one, and its name, as `DB` writes them but
+/// without the quotes
+pub(crate) fn schema_and_name<T, DB>() -> QueryResult<(Option<String>, String)>
+where
+    T: TableName<DB>,
+    DB: Backend,
+    DB::QueryBuilder: Default,
+{
+    let mut out = DB::QueryBuilder::default();
+    T::push_name(&mut out)?;
+
+    let mut identifiers = unquote(&out.finish());
+    let name = identifiers.pop().unwrap_or_default();
+
+    Ok((identifiers.pop(), name))
+}
+
+/// Splits `"schema"."table"` (or `` `schema`.`table` ``) into its identifiers,
+/// where a doubled quote inside an identifier stands for the quote itself
+fn unquote(quoted: &str) -> Vec<String> {
+    let mut identifiers = Vec::new();
+    let mut chars = quoted.chars().peekable();
+
+    while let Some(first) = chars.next() {
+        if first == '.' {
+            continue;
+        }
+
+        let mut identifier = String::new();
+        if first == '"' || first == '`' {
+            while let Some(c) = chars.next() {
+                if c == first && chars.next_if_eq(&first).is_none() {
+                    break;
+                }
+                identifier.push(c);
+            }
+        } else {
+            identifier.push(first);
+            while let Some(c) = chars.next_if(|&c| c != '.') {
+                identifier.push(c);
+            }
+        }
+
+        identifiers.push(identifier);
+    }
+
+    identifiers
+}
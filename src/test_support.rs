@@ -0,0 +1,124 @@
+//! Helpers for checking generated structs against an in-memory SQLite database
+
+use diesel::query_builder::{QueryBuilder, QueryFragment};
+use diesel::sql_types;
+use diesel::sqlite::{Sqlite, SqliteConnection, SqliteQueryBuilder};
+use diesel::{Column, Expression, QueryResult, QuerySource, RunQueryDsl, Table};
+
+#[doc(hidden)]
+pub use proptest;
+
+/// SQL types which can be turned into a SQLite column definition
+pub trait SqliteColumnType {
+    /// The SQLite type affinity used in the `CREATE TABLE` statement
+    const TYPE: &'static str;
+    /// Whether the column should be created without a `NOT NULL` constraint
+    const NULLABLE: bool = false;
+}
+
+macro_rules! sqlite_column_types {
+    ($($sql_type:ident => $affinity:literal),* $(,)?) => {
+        $(
+            impl SqliteColumnType for sql_types::$sql_type {
+                const TYPE: &'static str = $affinity;
+            }
+        )*
+    };
+}
+
+sqlite_column_types! {
+    Bool => "INTEGER",
+    SmallInt => "INTEGER",
+    Integer => "INTEGER",
+    BigInt => "INTEGER",
+    Float => "REAL",
+    Double => "REAL",
+    Numeric => "NUMERIC",
+    Text => "TEXT",
+    Binary => "BLOB",
+    Date => "TEXT",
+    Time => "TEXT",
+    Timestamp => "TEXT",
+    TimestamptzSqlite => "TEXT",
+}
+
+impl<T: SqliteColumnType> SqliteColumnType for sql_types::Nullable<T> {
+    const TYPE: &'static str = T::TYPE;
+    const NULLABLE: bool = true;
+}
+
+/// A tuple of columns which can be turned into SQLite column definitions
+pub trait SqliteColumns {
+    /// The column definitions of a `CREATE TABLE` statement, in order
+    fn column_definitions(primary_key: &str) -> Vec<String>;
+}
+
+fn column_definition<C>(primary_key: &str) -> String
+where
+    C: Column + Expression,
+    C::SqlType: SqliteColumnType,
+{
+    let mut definition = format!("`{}` {}", C::NAME, C::SqlType::TYPE);
+
+    if !C::SqlType::NULLABLE {
+        definition.push_str(" NOT NULL");
+    }
+
+    if C::NAME == primary_key {
+        definition.push_str(" PRIMARY KEY");
+    }
+
+    definition
+}
+
+macro_rules! sqlite_columns {
+    ($first:ident $(, $rest:ident)*) => {
+        impl<$first, $($rest),*> SqliteColumns for ($first, $($rest,)*)
+        where
+            $first: Column + Expression,
+            $first::SqlType: SqliteColumnType,
+            $(
+                $rest: Column + Expression,
+                $rest::SqlType: SqliteColumnType,
+            )*
+        {
+            fn column_definitions(primary_key: &str) -> Vec<String> {
+                vec![
+                    column_definition::<$first>(primary_key),
+                    $(column_definition::<$rest>(primary_key),)*
+                ]
+            }
+        }
+
+        sqlite_columns!($($rest),*);
+    };
+    () => {};
+}
+
+sqlite_columns!(
+    C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21,
+    C22, C23, C24, C25, C26, C27, C28, C29, C30, C31, C32
+);
+
+/// Creates `table` in the given SQLite database from its `table!` definition
+///
+/// The primary key is created as `INTEGER PRIMARY KEY` so that SQLite
+/// autoincrements it, and every column that isn't `Nullable` is `NOT NULL`.
+pub fn create_sqlite_table<T>(table: T, conn: &mut SqliteConnection) -> QueryResult<usize>
+where
+    T: Table,
+    T::PrimaryKey: Column,
+    T::AllColumns: SqliteColumns,
+    <T as QuerySource>::FromClause: QueryFragment<Sqlite>,
+{
+    let mut name = SqliteQueryBuilder::new();
+    table.from_clause().to_sql(&mut name, &Sqlite)?;
+
+    let sql = format!(
+        "CREATE TABLE {} ({})",
+        name.finish(),
+        T::AllColumns::column_definitions(<T::PrimaryKey as Column>::NAME).join(", ")
+    );
+
+    diesel::sql_query(sql).execute(conn)
+}